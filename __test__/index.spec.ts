@@ -1,11 +1,26 @@
 import { readFile } from 'node:fs/promises'
 import { join } from 'node:path'
 import { fileURLToPath } from 'node:url'
-import { writeFileSync, unlinkSync, mkdirSync, rmSync } from 'node:fs'
+import {
+  createReadStream,
+  writeFileSync,
+  unlinkSync,
+  mkdirSync,
+  readdirSync,
+  rmSync,
+  openSync,
+  writeSync,
+  ftruncateSync,
+  closeSync,
+  readFileSync,
+  existsSync,
+  statSync,
+} from 'node:fs'
+import { Readable } from 'node:stream'
 
 import test from 'ava'
 
-import { Archive, Builder } from '../index'
+import { Archive, Builder, EntryType, SkipReason, type Progress } from '../index'
 
 const __dirname = join(fileURLToPath(import.meta.url), '..')
 
@@ -16,6 +31,131 @@ test('should be able to read archive', (t) => {
   }
 })
 
+test('should be able to read archive asynchronously', async (t) => {
+  for (const format of ['src.tar', 'src.tar.gz', 'src.tar.bz2', 'src.tar.xz']) {
+    const archive = new Archive(join(__dirname, format))
+    const paths: string[] = []
+    for await (const entry of archive.entriesAsync()) {
+      paths.push(entry.path()!)
+      if (entry.path() === 'src/lib.rs') {
+        t.true(entry.asBytes().toString('utf-8').includes('napi'), `Should read src/lib.rs from ${format}`)
+      }
+    }
+    t.true(paths.includes('src/lib.rs'), `Should list src/lib.rs in ${format}`)
+  }
+})
+
+test('should refuse to use the archive while an async call holds it', async (t) => {
+  const tar = await readFile(join(__dirname, 'src.tar'))
+  let release!: () => void
+  const gate = new Promise<void>((resolve) => (release = resolve))
+  // The second `next()` waits on the worker thread for the rest of the input.
+  async function* chunks() {
+    yield tar.subarray(0, 512)
+    await gate
+    yield tar.subarray(512)
+  }
+  const archive = new Archive(chunks())
+  const entries = archive.entriesAsync()[Symbol.asyncIterator]()
+  const first = (await entries.next()).value!
+  const pending = entries.next()
+  const busy = { message: /in use by a pending asynchronous call/ }
+  t.throws(() => archive.list(), busy)
+  t.throws(() => archive.setMask(0o022), busy)
+  t.throws(() => archive.setLenient(true), busy)
+  t.throws(() => archive.entriesAsync(), busy)
+  t.throws(() => first.path(), busy)
+  release()
+  t.is((await pending).value!.path(), 'src/lib.rs')
+  t.is(first.path(), 'src/')
+})
+
+test('should keep the archive alive while a next() call is pending', async (t) => {
+  const tar = await readFile(join(__dirname, 'src.tar'))
+  let release!: () => void
+  const gate = new Promise<void>((resolve) => (release = resolve))
+  async function* chunks() {
+    yield tar.subarray(0, 512)
+    await gate
+    yield tar.subarray(512)
+  }
+  // Nothing references the archive nor the iterator while the second
+  // `next()` waits on the worker thread.
+  const pending = (async () => {
+    const entries = new Archive(chunks()).entriesAsync()[Symbol.asyncIterator]()
+    await entries.next()
+    return entries.next().then(({ value }) => value!.path())
+  })()
+  for (let i = 0; i < 5; i++) {
+    await new Promise((resolve) => setTimeout(resolve, 10))
+    globalThis.gc!()
+  }
+  release()
+  t.is(await pending, 'src/lib.rs')
+})
+
+test('should keep the entry and its archive alive while a stream read is pending', async (t) => {
+  const tar = await readFile(join(__dirname, 'src.tar'))
+  let release!: () => void
+  const gate = new Promise<void>((resolve) => (release = resolve))
+  // The headers of `src/` and `src/lib.rs`, then the content of `src/lib.rs`.
+  async function* chunks() {
+    yield tar.subarray(0, 1024)
+    await gate
+    yield tar.subarray(1024)
+  }
+  const pending = (async () => {
+    const entries = new Archive(chunks()).entriesAsync()[Symbol.asyncIterator]()
+    await entries.next()
+    const stream = (await entries.next()).value!.createReadStream()
+    const chunk = stream[Symbol.asyncIterator]().next()
+    return chunk.then(({ value }) => value.length)
+  })()
+  for (let i = 0; i < 5; i++) {
+    await new Promise((resolve) => setTimeout(resolve, 10))
+    globalThis.gc!()
+  }
+  release()
+  t.is(await pending, 1287)
+})
+
+test('should throw when an entry header is corrupted', async (t) => {
+  const archiveBuffer = await readFile(join(__dirname, 'src.tar'))
+  // Flip a byte in the header of `src/entry.rs`, the third entry.
+  archiveBuffer[2560 + 100] ^= 0xff
+  const paths: string[] = []
+  const error = t.throws(() => {
+    for (const entry of new Archive(archiveBuffer).entries()) {
+      paths.push(entry.path()!)
+    }
+  })
+  t.is(error?.message, 'Failed to read entry #2 at byte offset 2560: archive header checksum mismatch')
+  t.deepEqual(paths, ['src/', 'src/lib.rs'])
+  await t.throwsAsync(async () => {
+    for await (const _ of new Archive(archiveBuffer).entriesAsync()) {
+    }
+  }, { message: /Failed to read entry #2/ })
+
+  const lenient = new Archive(archiveBuffer)
+  lenient.setLenient(true)
+  t.deepEqual(
+    Array.from(lenient.entries(), (entry) => entry.path()),
+    ['src/', 'src/lib.rs'],
+  )
+})
+
+test('should throw when a compressed archive is truncated', async (t) => {
+  const archiveBuffer = await readFile(join(__dirname, 'src.tar.gz'))
+  const truncated = archiveBuffer.subarray(0, archiveBuffer.length - 200)
+  t.throws(
+    () => {
+      for (const _ of new Archive(truncated).entries()) {
+      }
+    },
+    { message: /incomplete deflate stream/ },
+  )
+})
+
 test('should be able to unpack archive', (t) => {
   if (process.env.NAPI_RS_FORCE_WASI) {
     t.pass('Skipping unpack test on WASI')
@@ -26,6 +166,251 @@ test('should be able to unpack archive', (t) => {
   t.pass()
 })
 
+test('should be able to unpack archive asynchronously', async (t) => {
+  if (process.env.NAPI_RS_FORCE_WASI) {
+    t.pass('Skipping unpack test on WASI')
+    return
+  }
+  const dest = join(__dirname, 'unpack-async')
+  const archive = new Archive(join(__dirname, 'src.tar'))
+  await archive.unpackAsync(dest)
+  const content = await readFile(join(dest, 'src', 'lib.rs'), 'utf-8')
+  t.true(content.includes('#![deny(clippy::all)]'))
+  rmSync(dest, { recursive: true, force: true })
+})
+
+test('should be able to unpack selected entries', async (t) => {
+  if (process.env.NAPI_RS_FORCE_WASI) {
+    t.pass('Skipping unpack test on WASI')
+    return
+  }
+  const dest = join(__dirname, 'unpack-entry')
+  mkdirSync(dest, { recursive: true })
+  const archive = new Archive(join(__dirname, 'src.tar'))
+  for (const entry of archive.entries()) {
+    if (entry.path() === 'src/lib.rs') {
+      entry.unpack(join(dest, 'lib.rs'))
+    } else if (entry.path() === 'src/entry.rs') {
+      t.true(entry.unpackIn(join(dest, 'in')))
+    }
+  }
+  t.true((await readFile(join(dest, 'lib.rs'), 'utf-8')).includes('#![deny(clippy::all)]'))
+  t.true((await readFile(join(dest, 'in', 'src', 'entry.rs'), 'utf-8')).includes('napi'))
+
+  const written: Record<string, boolean> = {}
+  for (const entry of new Archive(join(__dirname, 'traversal.tar')).entries()) {
+    written[entry.path()!] = entry.unpackIn(join(dest, 'traversal'))
+  }
+  t.deepEqual(written, { 'safe.txt': true, '../evil.txt': false })
+  rmSync(dest, { recursive: true, force: true })
+})
+
+test('should unpack only entries matching include and exclude globs', async (t) => {
+  if (process.env.NAPI_RS_FORCE_WASI) {
+    t.pass('Skipping unpack test on WASI')
+    return
+  }
+  const dest = join(__dirname, 'unpack-globs')
+  new Archive(join(__dirname, 'src.tar')).unpack(join(dest, 'include'), { include: ['src/lib.rs'] })
+  t.deepEqual(readdirSync(join(dest, 'include', 'src')), ['lib.rs'])
+
+  await new Archive(join(__dirname, 'src.tar')).unpackAsync(join(dest, 'exclude'), {
+    include: ['/src'],
+    exclude: ['lib.rs'],
+  })
+  t.deepEqual(readdirSync(join(dest, 'exclude', 'src')), ['entry.rs'])
+  rmSync(dest, { recursive: true, force: true })
+})
+
+test('should strip and rename entry paths when unpacking', async (t) => {
+  if (process.env.NAPI_RS_FORCE_WASI) {
+    t.pass('Skipping unpack test on WASI')
+    return
+  }
+  const dest = join(__dirname, 'unpack-rename')
+  await new Archive(join(__dirname, 'links.tar')).unpackAsync(dest, {
+    stripComponents: 1,
+    rename: (path) => (path === 'long' ? null : path === 'escape' ? '../escape' : path),
+  })
+  t.deepEqual(readdirSync(dest).sort(), ['bin', 'hard', 'lib'])
+  t.is(await readFile(join(dest, 'hard'), 'utf-8'), await readFile(join(dest, 'lib', 'data.txt'), 'utf-8'))
+  t.is(await readFile(join(dest, 'bin', 'tool'), 'utf-8'), 'data\n')
+  rmSync(dest, { recursive: true, force: true })
+})
+
+test('should report unpack progress', async (t) => {
+  if (process.env.NAPI_RS_FORCE_WASI) {
+    t.pass('Skipping unpack test on WASI')
+    return
+  }
+  const dest = join(__dirname, 'unpack-progress')
+  const reports: Progress[] = []
+  new Archive(join(__dirname, 'src.tar.gz')).unpack(dest, {
+    onProgress: (progress) => reports.push(progress),
+    progressInterval: 0,
+  })
+  t.deepEqual(
+    reports.map((progress) => [progress.entriesDone, progress.currentPath]),
+    [
+      [1, 'src/lib.rs'],
+      [2, 'src/entry.rs'],
+      [3, 'src/'],
+    ],
+  )
+  const last = reports[reports.length - 1]
+  t.true(last.bytesDone > 0n)
+  t.is(last.compressedBytesRead, BigInt((await readFile(join(__dirname, 'src.tar.gz'))).length))
+
+  // The progress calls of `unpackAsync` are queued, so they may arrive after
+  // its promise resolves.
+  let reportLast: (progress: Progress) => void
+  const lastReport = new Promise<Progress>((resolve) => (reportLast = resolve))
+  await new Archive(join(__dirname, 'src.tar.xz')).unpackAsync(dest, {
+    onProgress: (progress) => {
+      if (progress.entriesDone === 3) {
+        reportLast(progress)
+      }
+    },
+  })
+  t.is((await lastReport).currentPath, 'src/')
+  rmSync(dest, { recursive: true, force: true })
+})
+
+test('should report written and rejected entries', async (t) => {
+  if (process.env.NAPI_RS_FORCE_WASI) {
+    t.pass('Skipping unpack test on WASI')
+    return
+  }
+  const dest = join(__dirname, 'unpack-report')
+  const report = new Archive(join(__dirname, 'traversal.tar')).unpack(dest)
+  t.deepEqual(report.written, ['safe.txt'])
+  t.deepEqual(report.skipped, [{ path: '../evil.txt', reason: SkipReason.PathTraversal }])
+  t.is(report.entriesWritten, 1)
+  t.is(report.entriesSkipped, 1)
+
+  const archive = new Archive(join(__dirname, 'traversal.tar'))
+  archive.setOverwrite(false)
+  const again = await archive.unpackAsync(dest)
+  t.deepEqual(
+    again.skipped.map((entry) => entry.reason),
+    [SkipReason.ExistingFileKept, SkipReason.PathTraversal],
+  )
+  t.is(again.bytesWritten, 0n)
+  rmSync(dest, { recursive: true, force: true })
+})
+
+test('should enforce the link policy when unpacking', async (t) => {
+  if (process.env.NAPI_RS_FORCE_WASI) {
+    t.pass('Skipping unpack test on WASI')
+    return
+  }
+  const dest = join(__dirname, 'unpack-links')
+  t.throws(() => new Archive(join(__dirname, 'links.tar')).unpack(dest, { links: 'reject' }), {
+    message: 'Refusing to unpack `pkg/bin/tool`: links are rejected',
+  })
+  await t.throwsAsync(new Archive(join(__dirname, 'links.tar')).unpackAsync(dest, { links: 'contain' }), {
+    message: 'Refusing to unpack `pkg/escape`: its target `../../outside.txt` is outside of the destination',
+  })
+  const report = new Archive(join(__dirname, 'links.tar')).unpack(dest, {
+    links: 'contain',
+    exclude: ['escape'],
+  })
+  t.true(report.written.includes('pkg/hard'))
+  rmSync(dest, { recursive: true, force: true })
+})
+
+test('should reject unpackAsync when the signal is aborted', async (t) => {
+  if (process.env.NAPI_RS_FORCE_WASI) {
+    t.pass('Skipping unpack test on WASI')
+    return
+  }
+  const builder = new Builder()
+  for (let i = 0; i < 2000; i++) {
+    builder.appendData(`files/${i}.txt`, Buffer.alloc(4096, 'a'))
+  }
+  const dest = join(__dirname, 'unpack-async-abort')
+  const data = Buffer.from(builder.finish()!)
+  await t.throwsAsync(new Archive(data).unpackAsync(dest, { signal: AbortSignal.abort() }), {
+    code: 'Cancelled',
+    message: /aborted after 0 entries/,
+  })
+  t.false(existsSync(dest))
+
+  const controller = new AbortController()
+  let onabortCalled = false
+  controller.signal.onabort = () => {
+    onabortCalled = true
+  }
+  const promise = new Archive(data).unpackAsync(dest, { signal: controller.signal })
+  controller.abort()
+  await t.throwsAsync(promise, { message: /aborted after \d+ entries/ })
+  t.true(onabortCalled)
+  rmSync(dest, { recursive: true, force: true })
+
+  const again = new AbortController()
+  const aborted = new Archive(data).unpackAsync(dest, {
+    signal: again.signal,
+    progressInterval: 0,
+    onProgress: ({ entriesDone }) => {
+      if (entriesDone >= 10) {
+        again.abort()
+      }
+    },
+  })
+  await t.throwsAsync(aborted, { message: /aborted after \d+ entries/ })
+  rmSync(dest, { recursive: true, force: true })
+})
+
+test('should be able to read archive from a stream', async (t) => {
+  for (const format of ['src.tar', 'src.tar.gz', 'src.tar.zst']) {
+    const archive = new Archive(createReadStream(join(__dirname, format), { highWaterMark: 1024 }))
+    const paths: string[] = []
+    for await (const entry of archive.entriesAsync()) {
+      paths.push(entry.path()!)
+    }
+    t.true(paths.includes('src/lib.rs'), `Should list src/lib.rs in ${format}`)
+  }
+  const archive = new Archive(createReadStream(join(__dirname, 'src.tar')))
+  t.throws(() => archive.entries())
+})
+
+test('should be able to unpack archive from a stream', async (t) => {
+  if (process.env.NAPI_RS_FORCE_WASI) {
+    t.pass('Skipping unpack test on WASI')
+    return
+  }
+  const dest = join(__dirname, 'unpack-stream')
+  const archive = new Archive(Readable.from([await readFile(join(__dirname, 'src.tar.bz2'))]))
+  await archive.unpackAsync(dest)
+  const content = await readFile(join(dest, 'src', 'lib.rs'), 'utf-8')
+  t.true(content.includes('#![deny(clippy::all)]'))
+  rmSync(dest, { recursive: true, force: true })
+})
+
+test('should unpack more streams at once than the libuv thread pool has threads', async (t) => {
+  if (process.env.NAPI_RS_FORCE_WASI) {
+    t.pass('Skipping unpack test on WASI')
+    return
+  }
+  // `fs.createReadStream` reads on the libuv thread pool.
+  const count = Number(process.env.UV_THREADPOOL_SIZE ?? 4) + 2
+  const dests = Array.from({ length: count }, (_, i) => join(__dirname, `unpack-streams-${i}`))
+  const reports = await Promise.all(
+    dests.map((dest) => new Archive(createReadStream(join(__dirname, 'src.tar.gz'))).unpackAsync(dest)),
+  )
+  t.deepEqual(
+    reports.map((report) => report.entriesWritten),
+    dests.map(() => 3),
+  )
+  const verified = await Promise.all(
+    dests.map(() => new Archive(createReadStream(join(__dirname, 'src.tar.gz'))).verifyAsync()),
+  )
+  t.true(verified.every((report) => report.ok))
+  for (const dest of dests) {
+    rmSync(dest, { recursive: true, force: true })
+  }
+})
+
 test('should be able to create archive from Buffer', async (t) => {
   const archiveBuffer = await readFile(join(__dirname, 'src.tar'))
   const archive = new Archive(archiveBuffer)
@@ -34,6 +419,170 @@ test('should be able to create archive from Buffer', async (t) => {
   }
 })
 
+test('should be able to create archive from a Uint8Array view', async (t) => {
+  const file = await readFile(join(__dirname, 'src.tar.gz'))
+  const padded = new Uint8Array(file.length + 16)
+  padded.set(file, 16)
+  const archive = new Archive(padded.subarray(16))
+  const paths: string[] = []
+  for (const entry of archive.entries()) {
+    paths.push(entry.path()!)
+  }
+  t.true(paths.includes('src/lib.rs'))
+})
+
+test('should be able to read pre-POSIX tar without ustar magic', async (t) => {
+  for (const input of [join(__dirname, 'v7.tar'), await readFile(join(__dirname, 'v7.tar'))]) {
+    const archive = new Archive(input)
+    const contents: Record<string, string> = {}
+    for (const entry of archive.entries()) {
+      contents[entry.path()!] = entry.asBytes().toString('utf-8')
+    }
+    t.deepEqual(contents, { 'hello.txt': 'old school\n' })
+  }
+})
+
+test('should use the explicit compression option', async (t) => {
+  const archive = new Archive(await readFile(join(__dirname, 'src.tar.gz')), { compression: 'gzip' })
+  const paths: string[] = []
+  for (const entry of archive.entries()) {
+    paths.push(entry.path()!)
+  }
+  t.true(paths.includes('src/lib.rs'))
+  // @ts-expect-error invalid compression
+  t.throws(() => new Archive(join(__dirname, 'src.tar'), { compression: 'rar' }))
+})
+
+test('should enforce archive limits', async (t) => {
+  t.throws(() => new Archive(join(__dirname, 'src.tar'), { limits: { maxEntries: 1 } }).list(), {
+    code: 'LimitExceeded',
+    message: /^LimitExceeded: .*maxEntries/,
+  })
+  t.throws(() => new Archive(join(__dirname, 'src.tar'), { limits: { maxPathLength: 5 } }).list(), {
+    code: 'LimitExceeded',
+    message: /^LimitExceeded: .*maxPathLength/,
+  })
+  const buffer = await readFile(join(__dirname, 'src.tar.gz'))
+  const dst = join(__dirname, 'limited')
+  t.throws(() => new Archive(buffer, { limits: { maxTotalBytes: 1024 } }).unpack(dst), {
+    code: 'LimitExceeded',
+    message: /^LimitExceeded: .*maxTotalBytes/,
+  })
+  await t.throwsAsync(new Archive(buffer, { limits: { maxTotalBytes: 1024 } }).unpackAsync(dst), {
+    code: 'LimitExceeded',
+  })
+  rmSync(dst, { recursive: true, force: true })
+  t.is(new Archive(buffer, { limits: { maxEntries: 100, maxPathLength: 100 } }).list().length, 3)
+})
+
+test('should verify archive integrity without extracting', async (t) => {
+  const tar = await readFile(join(__dirname, 'src.tar'))
+  const report = new Archive(tar).verify()
+  t.true(report.ok)
+  t.is(report.entries, 3)
+  t.deepEqual(report.problems, [])
+
+  const corrupted = Buffer.from(tar)
+  corrupted[512 + 10] ^= 0xff
+  const trailing = Buffer.concat([corrupted, Buffer.from('garbage')])
+  const { ok, problems } = await new Archive(trailing).verifyAsync()
+  t.false(ok)
+  t.deepEqual(
+    problems.map(({ kind, offset }) => [kind, offset]),
+    [
+      ['checksumMismatch', 512n],
+      ['trailingData', BigInt(tar.length)],
+    ],
+  )
+
+  const gz = Buffer.from(await readFile(join(__dirname, 'src.tar.gz')))
+  gz[gz.length - 6] ^= 0xff
+  t.deepEqual(
+    new Archive(gz).verify().problems.map(({ kind }) => kind),
+    ['corruptCompression'],
+  )
+})
+
+test('should round-trip sparse files', (t) => {
+  if (process.env.NAPI_RS_FORCE_WASI) {
+    t.pass('Skipping sparse test on WASI')
+    return
+  }
+  const sparseFile = join(__dirname, 'sparse.img')
+  const fd = openSync(sparseFile, 'w')
+  ftruncateSync(fd, 8 * 1024 * 1024)
+  writeSync(fd, 'head', 0)
+  writeSync(fd, 'middle', 4 * 1024 * 1024)
+  closeSync(fd)
+  const expected = readFileSync(sparseFile)
+
+  const builder = new Builder()
+  builder.appendFile('sparse.img', sparseFile)
+  const data = Buffer.from(builder.finish()!)
+  unlinkSync(sparseFile)
+
+  t.deepEqual(
+    new Archive(data).list().map(({ path, size }) => [path, size]),
+    [['sparse.img', BigInt(expected.length)]],
+  )
+  for (const entry of new Archive(data).entries()) {
+    t.true(entry.asBytes().equals(expected))
+  }
+  const dest = join(__dirname, 'unpack-sparse')
+  t.deepEqual(new Archive(data).unpack(dest).written, ['sparse.img'])
+  t.true(readFileSync(join(dest, 'sparse.img')).equals(expected))
+  rmSync(dest, { recursive: true, force: true })
+})
+
+test('should apply ownership and xattrs settings to sparse entries', (t) => {
+  if (process.env.NAPI_RS_FORCE_WASI || process.platform === 'win32') {
+    t.pass('Skipping sparse test on WASI and Windows')
+    return
+  }
+  // A PAX 1.0 sparse file owned by 1234:5678, with a `user.origin` xattr.
+  const dest = join(__dirname, 'unpack-sparse-settings')
+  const archive = new Archive(join(__dirname, 'xattr-sparse.tar'))
+  archive.setUnpackXattrs(true)
+  // Only root may give files away.
+  const root = process.getuid?.() === 0
+  archive.setPreserveOwnerships(root)
+  t.deepEqual(archive.unpack(dest).written, ['sparse.bin'])
+  const stats = statSync(join(dest, 'sparse.bin'))
+  t.is(stats.size, 65536)
+  if (root) {
+    t.deepEqual([stats.uid, stats.gid], [1234, 5678])
+  }
+  rmSync(dest, { recursive: true, force: true })
+})
+
+test('should append sparse files with long names', (t) => {
+  if (process.env.NAPI_RS_FORCE_WASI) {
+    t.pass('Skipping sparse test on WASI')
+    return
+  }
+  const sparseFile = join(__dirname, 'sparse-long.img')
+  const fd = openSync(sparseFile, 'w')
+  ftruncateSync(fd, 1024 * 1024)
+  writeSync(fd, 'data', 512 * 1024)
+  closeSync(fd)
+  const expected = readFileSync(sparseFile)
+
+  const name = `dir/${'a'.repeat(150)}.img`
+  const builder = new Builder()
+  builder.appendFile(name, sparseFile)
+  const data = Buffer.from(builder.finish()!)
+  unlinkSync(sparseFile)
+
+  t.deepEqual(
+    new Archive(data).list().map(({ path, size }) => [path, size]),
+    [[name, BigInt(expected.length)]],
+  )
+  const dest = join(__dirname, 'unpack-sparse-long')
+  t.deepEqual(new Archive(data).unpack(dest).written, [name])
+  t.true(readFileSync(join(dest, name)).equals(expected))
+  rmSync(dest, { recursive: true, force: true })
+})
+
 test('should be able to handle tar.gz', (t) => {
   const archive = new Archive(join(__dirname, 'src.tar.gz'))
   for (const entry of archive.entries()) {
@@ -55,6 +604,185 @@ test('should be able to handle tar.xz', (t) => {
   }
 })
 
+test('should be able to handle tar.zst', async (t) => {
+  for (const input of [join(__dirname, 'src.tar.zst'), await readFile(join(__dirname, 'src.tar.zst'))]) {
+    const archive = new Archive(input)
+    for (const entry of archive.entries()) {
+      t.is(typeof entry.path(), 'string')
+    }
+  }
+})
+
+test('should be able to handle multi-frame tar.zst', async (t) => {
+  for (const input of [
+    join(__dirname, 'src.multi-frame.tar.zst'),
+    await readFile(join(__dirname, 'src.multi-frame.tar.zst')),
+  ]) {
+    const archive = new Archive(input)
+    const paths: string[] = []
+    for (const entry of archive.entries()) {
+      paths.push(entry.path()!)
+      if (entry.path() === 'src/entry.rs') {
+        t.true(entry.asBytes().toString('utf-8').includes('use'))
+      }
+    }
+    t.deepEqual(paths, ['src/', 'src/lib.rs', 'src/entry.rs'])
+  }
+})
+
+test('should read every member of concatenated compressed archives', (t) => {
+  for (const format of ['concat.tar.gz', 'concat.tar.bz2', 'concat.tar.xz', 'concat.tar.zst', 'concat.tar.lz4']) {
+    const archive = new Archive(join(__dirname, format))
+    archive.setIgnoreZeros(true)
+    const contents: Record<string, string> = {}
+    for (const entry of archive.entries()) {
+      contents[entry.path()!] = entry.asBytes().toString('utf-8')
+    }
+    t.deepEqual(
+      contents,
+      { 'a/': '', 'a/one.txt': 'first member\n', 'b/': '', 'b/two.txt': 'second member\n' },
+      `Should list both members of ${format}`,
+    )
+  }
+})
+
+test('should be able to handle tar.lz4 and tar.Z', async (t) => {
+  for (const format of ['src.tar.lz4', 'src.tar.Z']) {
+    for (const input of [join(__dirname, format), await readFile(join(__dirname, format))]) {
+      const archive = new Archive(input)
+      const paths: string[] = []
+      for (const entry of archive.entries()) {
+        paths.push(entry.path()!)
+        if (entry.path() === 'src/lib.rs') {
+          t.true(entry.asBytes().toString('utf-8').includes('#![deny(clippy::all)]'), `Should decode ${format}`)
+        }
+      }
+      t.deepEqual(paths, ['src/', 'src/lib.rs', 'src/entry.rs'])
+    }
+  }
+})
+
+test('should be able to handle tar.xz from Buffer', async (t) => {
+  const expected = new Map<string, Buffer>()
+  for (const entry of new Archive(join(__dirname, 'src.tar')).entries()) {
+    expected.set(entry.path()!, entry.asBytes())
+  }
+  const archive = new Archive(await readFile(join(__dirname, 'src.tar.xz')))
+  let count = 0
+  for (const entry of archive.entries()) {
+    t.deepEqual(entry.asBytes(), expected.get(entry.path()!), `Content of ${entry.path()} should match`)
+    count++
+  }
+  t.is(count, expected.size)
+})
+
+test('should expose PAX extended header records', (t) => {
+  const archive = new Archive(join(__dirname, 'pax.tar'))
+  const records: Record<string, Record<string, string | undefined>> = {}
+  for (const entry of archive.entries()) {
+    records[entry.path()!] = Object.fromEntries(
+      entry.paxExtensions().map((record) => [record.key, record.valueString]),
+    )
+  }
+  t.deepEqual(records['a.txt'], {
+    comment: 'release 1.0',
+    'VENDOR.build': '43',
+    'SCHILY.xattr.user.checksum': 'abc',
+    mtime: '1700000000.25',
+  })
+  t.deepEqual(records['b.txt'], {
+    comment: 'release 1.0',
+    'VENDOR.build': '42',
+    mtime: '1700000000.25',
+  })
+})
+
+test('should resolve link names and targets', (t) => {
+  const archive = new Archive(join(__dirname, 'links.tar'))
+  const links: Record<string, [string | null, string | null]> = {}
+  for (const entry of archive.entries()) {
+    links[entry.path()!] = [entry.linkName(), entry.linkTarget()]
+  }
+  const longTarget = 'deeply/'.repeat(20) + 'target.txt'
+  t.deepEqual(links['pkg/lib/data.txt'], [null, null])
+  t.deepEqual(links['pkg/bin/tool'], ['../lib/./data.txt', 'pkg/lib/data.txt'])
+  t.deepEqual(links['pkg/long'], [longTarget, `pkg/${longTarget}`])
+  t.deepEqual(links['pkg/escape'], ['../../outside.txt', '../outside.txt'])
+  t.deepEqual(links['pkg/hard'], ['pkg/lib/data.txt', 'pkg/lib/data.txt'])
+})
+
+test('should expose raw positions of entries', async (t) => {
+  const raw = await readFile(join(__dirname, 'src.tar'))
+  const positions: Record<string, [bigint, bigint, bigint]> = {}
+  for (const entry of new Archive(raw).entries()) {
+    positions[entry.path()!] = [entry.rawHeaderPosition(), entry.rawFilePosition(), entry.paddedSize()]
+    const start = Number(entry.rawFilePosition())
+    const content = entry.asBytes()
+    t.true(raw.subarray(start, start + content.length).equals(content))
+  }
+  t.deepEqual(positions['src/lib.rs'], [512n, 1024n, 1536n])
+  t.deepEqual(positions['src/entry.rs'], [2560n, 3072n, 1024n])
+
+  // Old GNU sparse entries only store their data segments, 8 KiB out of 64.
+  const sparse = new Archive(join(__dirname, 'gnu-sparse.tar')).list().map(({ path, size }) => [path, size])
+  t.deepEqual(sparse, [
+    ['sparse.bin', 65536n],
+    ['after.txt', 6n],
+  ])
+  const [entry, after] = new Archive(join(__dirname, 'gnu-sparse.tar')).entries()
+  t.deepEqual([entry.rawFilePosition(), entry.paddedSize()], [512n, 8192n])
+  t.is(after.rawHeaderPosition(), entry.rawFilePosition() + entry.paddedSize())
+})
+
+test('should list entry metadata in one call', (t) => {
+  const list = new Archive(join(__dirname, 'links.tar')).list()
+  t.deepEqual(
+    list.map((entry) => entry.path),
+    ['pkg/', 'pkg/lib/', 'pkg/lib/data.txt', 'pkg/bin/tool', 'pkg/long', 'pkg/escape', 'pkg/hard'],
+  )
+  const data = list.find((entry) => entry.path === 'pkg/lib/data.txt')!
+  t.is(data.type, EntryType.Regular)
+  t.is(data.size, 5n)
+  t.is(data.mode, 0o644)
+  t.is(data.mtime, 1700000000n)
+  const tool = list.find((entry) => entry.path === 'pkg/bin/tool')!
+  t.is(tool.type, EntryType.Symlink)
+  t.is(tool.linkName, '../lib/./data.txt')
+  const selected = new Archive(join(__dirname, 'links.tar')).list({ include: ['pkg/lib'], exclude: ['*.txt'] })
+  t.deepEqual(selected.map((entry) => entry.path), ['pkg/lib/'])
+})
+
+test('should read the whole archive into memory', async (t) => {
+  const map = new Archive(join(__dirname, 'links.tar')).toMap()
+  t.true(map instanceof Map)
+  t.is(map.get('pkg/lib/data.txt')?.content?.toString(), 'data\n')
+  t.is(map.get('pkg/lib/')?.type, EntryType.Directory)
+  t.is(map.get('pkg/bin/tool')?.linkName, '../lib/./data.txt')
+  t.is(map.get('pkg/bin/tool')?.content, undefined)
+
+  const tar = await readFile(join(__dirname, 'src.tar'))
+  const object = new Archive(tar).extractToObject({ include: ['*.rs'] })
+  t.deepEqual(Object.keys(object), ['src/lib.rs', 'src/entry.rs'])
+  t.is(BigInt(object['src/lib.rs'].content!.length), object['src/lib.rs'].size)
+  t.throws(() => new Archive(tar).toMap({ maxTotalSize: 10 }), {
+    code: 'LimitExceeded',
+    message: /maxTotalSize/,
+  })
+})
+
+test('should not reserve memory from forged sizes when reading into memory', async (t) => {
+  const tar = Buffer.from(await readFile(join(__dirname, 'src.tar')))
+  // Give `src/lib.rs` a base-256 size of 2^60 bytes.
+  const header = tar.subarray(512, 1024)
+  header.fill(0, 124, 136)
+  header[124] = 0x80
+  header.writeBigUInt64BE(1n << 60n, 128)
+  header.fill(0x20, 148, 156)
+  const checksum = header.reduce((sum, byte) => sum + byte, 0)
+  header.write(`${checksum.toString(8).padStart(6, '0')}\0 `, 148, 'latin1')
+  t.throws(() => new Archive(tar).toMap(), { message: /unexpected EOF/ })
+})
+
 test('should be able to extract single file with asBytes', (t) => {
   const archive = new Archive(join(__dirname, 'src.tar'))
   for (const entry of archive.entries()) {
@@ -74,6 +802,55 @@ test('should be able to extract single file with asBytes', (t) => {
   t.fail('Could not find src/lib.rs in the archive')
 })
 
+test('should be able to read entry content in chunks', (t) => {
+  const archive = new Archive(join(__dirname, 'src.tar'))
+  for (const entry of archive.entries()) {
+    if (entry.path() === 'src/lib.rs') {
+      const chunks: Buffer[] = []
+      let chunk: Buffer | null
+      while ((chunk = entry.read(100))) {
+        t.true(chunk.length <= 100)
+        chunks.push(chunk)
+      }
+      t.true(chunks.length > 1)
+      t.true(Buffer.concat(chunks).toString('utf-8').includes('#![deny(clippy::all)]'))
+      t.is(entry.read(), null)
+    }
+  }
+})
+
+test('should be able to stream entry content', async (t) => {
+  const archive = new Archive(createReadStream(join(__dirname, 'src.tar.gz')))
+  for await (const entry of archive.entriesAsync()) {
+    if (entry.path() === 'src/lib.rs') {
+      const chunks: Buffer[] = []
+      for await (const chunk of entry.createReadStream()) {
+        chunks.push(chunk)
+      }
+      t.true(Buffer.concat(chunks).toString('utf-8').includes('#![deny(clippy::all)]'))
+    }
+  }
+})
+
+test('should refuse to read an entry while its stream is pending', async (t) => {
+  const tar = await readFile(join(__dirname, 'src.tar'))
+  let release!: () => void
+  const gate = new Promise<void>((resolve) => (release = resolve))
+  async function* chunks() {
+    yield tar.subarray(0, 1100)
+    await gate
+    yield tar.subarray(1100)
+  }
+  const entries = new Archive(chunks()).entriesAsync()[Symbol.asyncIterator]()
+  await entries.next()
+  const entry = (await entries.next()).value!
+  const content = entry.createReadStream().toArray()
+  await new Promise((resolve) => setImmediate(resolve))
+  t.throws(() => entry.read(), { message: /in use by a pending asynchronous call/ })
+  release()
+  t.true(Buffer.concat(await content).toString('utf-8').includes('napi'))
+})
+
 test('should be able to extract multiple files with asBytes', (t) => {
   const archive = new Archive(join(__dirname, 'src.tar'))
   const extractedFiles = new Map<string, Buffer>()
@@ -99,7 +876,7 @@ test('should be able to extract multiple files with asBytes', (t) => {
 })
 
 test('should work with asBytes on compressed archives', async (t) => {
-  const formats = ['src.tar.gz', 'src.tar.bz2', 'src.tar.xz']
+  const formats = ['src.tar.gz', 'src.tar.bz2', 'src.tar.xz', 'src.tar.zst']
 
   for (const format of formats) {
     const archive = new Archive(join(__dirname, format))
@@ -316,6 +1093,20 @@ test('Builder - should append directories', (t) => {
   rmSync(testDir, { recursive: true, force: true })
 })
 
+test('Builder - should report appendDirAll progress', (t) => {
+  const builder = new Builder()
+  const paths: string[] = []
+  builder.appendDirAll('pkg', join(__dirname, '..', 'src'), {
+    onProgress: (progress) => paths.push(progress.currentPath!),
+    progressInterval: 0,
+  })
+  const archive = new Archive(Buffer.from(builder.finish()!))
+  t.deepEqual(
+    paths,
+    archive.list().map((entry) => entry.path),
+  )
+})
+
 test('Builder - should handle mixed content types', (t) => {
   if (process.env.NAPI_RS_FORCE_WASI) {
     t.pass('Skipping mixed content types test on WASI')
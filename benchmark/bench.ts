@@ -35,6 +35,12 @@ b.add('@napi-rs/tar', () => {
   }
 })
 
+b.add('@napi-rs/tar list()', () => {
+  const archiveBuffer = readFileSync(ARCHIVE_PATH)
+  const archive = new Archive(archiveBuffer)
+  archive.list()
+})
+
 b.add('node-tar', () => {
   list({
     file: join(__dirname, '..', '__test__', 'src.tar.gz'),
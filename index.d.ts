@@ -1,9 +1,61 @@
 /* auto-generated by NAPI-RS */
 /* eslint-disable */
 export declare class Archive {
-  /** Create a new archive with the underlying path. */
-  constructor(input: string | Uint8Array)
+  /**
+   * Create a new archive with the underlying path.
+   *
+   * A `Buffer` input is read in place rather than copied, so it must not be
+   * modified while the archive is in use.
+   *
+   * The input can also be an async iterable of chunks, such as a Node.js
+   * `Readable`. Such an archive is read lazily, one chunk at a time, and only
+   * supports `entriesAsync` and `unpackAsync`.
+   *
+   * Set `limits` in `options` when reading untrusted archives.
+   */
+  constructor(input: string | Uint8Array | AsyncIterable<Uint8Array>, options?: ArchiveOptions)
   entries(): Entries
+  /**
+   * Returns the metadata of all the entries of this archive.
+   *
+   * The archive is walked in a single call, which is much cheaper than
+   * calling `path()` and `header()` on each entry of `entries()`. Entry
+   * contents are skipped.
+   *
+   * Use the `include` and `exclude` options to only list some entries.
+   */
+  list(options?: ListOptions | undefined | null): Array<EntryMetadata>
+  /**
+   * Reads the whole archive into memory, as a `Map` from entry paths to
+   * their content and metadata.
+   *
+   * Regular files come with their `content`. Directories, symlinks and hard
+   * links are metadata-only records, and other entry types are left out. A
+   * path occurring more than once maps to its last entry, as if the archive
+   * was unpacked.
+   *
+   * Nothing is written to disk, which makes this usable where `unpack` is
+   * not, such as in the WebAssembly build. Use `include`, `exclude` and
+   * `maxTotalSize` in `options` to bound the memory used.
+   */
+  toMap(options?: ExtractOptions | undefined | null): Map<string, ExtractedEntry>
+  /**
+   * Reads the whole archive into memory like `toMap`, as a plain object
+   * keyed by entry paths.
+   */
+  extractToObject(options?: ExtractOptions | undefined | null): Record<string, ExtractedEntry>
+  /**
+   * Returns an async iterator over the entries of this archive, to be used
+   * with `for await`.
+   *
+   * Reading and decompressing each header happens on a worker thread, so
+   * listing a large compressed archive does not block the event loop.
+   *
+   * Entries must be processed in order: read the content of an entry before
+   * requesting the next one. While a `next()` call is pending, the other
+   * methods of the archive and of its entries throw.
+   */
+  entriesAsync(): AsyncEntries
   /**
    * Unpacks the contents tarball into the specified `dst`.
    *
@@ -14,8 +66,42 @@ export declare class Archive {
    * This operation is relatively sensitive in that it will not write files
    * outside of the path specified by `dst`. Files in the archive which have
    * a '..' in their path are skipped during the unpacking process.
+   *
+   * Use the `include` and `exclude` options to only unpack some entries, and
+   * `stripComponents` or `rename` to unpack them under another path.
+   *
+   * Returns the list of written entries, and of the entries which were
+   * rejected, such as those with a '..' in their path.
    */
-  unpack(to: string): void
+  unpack(to: string, options?: UnpackOptions | undefined | null): UnpackReport
+  /**
+   * Unpacks the contents tarball into the specified `dst` on a worker
+   * thread.
+   *
+   * This behaves like `unpack`, but decompression and file writes happen off
+   * the JavaScript main thread. Pass an `AbortSignal` in `options` to stop
+   * the extraction between two entries; the returned promise then rejects
+   * with an error reporting how many entries were written before the abort,
+   * none if it was aborted before the extraction started.
+   *
+   * The archive is consumed by this call, it yields no entries afterwards.
+   */
+  unpackAsync(to: string, options?: UnpackOptions | undefined | null): Promise<UnpackReport>
+  /**
+   * Reads the whole archive to check its integrity, without extracting it.
+   *
+   * Every header and the content of every entry are read to the end. Header
+   * checksums, truncation, a missing end-of-archive marker, data after it
+   * and errors of the compressed stream, such as checksum failures, are
+   * reported with their offset in the decompressed archive instead of being
+   * thrown. Only errors reading the input and exceeded limits are thrown.
+   *
+   * The archive is consumed by this call, so verify it before reading any
+   * entry.
+   */
+  verify(): VerifyReport
+  /** Checks the integrity of the archive like `verify`, on a worker thread. */
+  verifyAsync(): Promise<VerifyReport>
   /**
    * Set the mask of the permission bits when unpacking this entry.
    *
@@ -55,7 +141,12 @@ export declare class Archive {
    * Unix.
    */
   setPreserveOwnerships(preserveOwnerships: boolean): void
-  /** Indicate whether files and symlinks should be overwritten on extraction. */
+  /**
+   * Indicate whether files and symlinks should be overwritten on extraction.
+   *
+   * When disabled, entries whose destination exists are skipped by `unpack`
+   * and listed in its report.
+   */
   setOverwrite(overwrite: boolean): void
   /**
    * Indicate whether access time information is preserved when unpacking
@@ -68,9 +159,29 @@ export declare class Archive {
    * Ignore zeroed headers, which would otherwise indicate to the archive that it has no more
    * entries.
    *
-   * This can be used in case multiple tar archives have been concatenated together.
+   * This can be used in case multiple tar archives have been concatenated together,
+   * compressed or not (e.g. `cat a.tar.gz b.tar.gz`).
    */
   setIgnoreZeros(ignoreZeros: boolean): void
+  /**
+   * Indicate whether `entries` and `entriesAsync` silently stop at an entry
+   * which can't be read, such as a corrupted header or a truncated stream.
+   *
+   * This flag is disabled by default: the iteration throws an error naming
+   * the index of the entry and its byte offset in the uncompressed archive
+   * instead.
+   */
+  setLenient(lenient: boolean): void
+}
+
+/**
+ * This type implements JavaScript's async iterable protocol.
+ * It can be used with `for await...of` loops.
+ *
+ * @see https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Iteration_protocols#the_async_iterator_and_async_iterable_protocols
+ */
+export declare class AsyncEntries {
+  [Symbol.asyncIterator](): AsyncGenerator<Entry, void, undefined>
 }
 
 export declare class Builder {
@@ -85,6 +196,10 @@ export declare class Builder {
    * This function will open the file specified by `src` and add it to the
    * archive as `name`. The `name` specified is the name that will be used
    * inside the archive.
+   *
+   * On Linux, Android and FreeBSD, files with holes are detected with
+   * `SEEK_DATA` and `SEEK_HOLE`, and stored as PAX 1.0 sparse entries which
+   * only hold their data.
    */
   appendFile(name: string, src: string): void
   /**
@@ -92,9 +207,9 @@ export declare class Builder {
    *
    * This function will recursively add all files and directories in the
    * specified `src` directory to the archive, preserving their relative
-   * paths under `name`.
+   * paths under `name`. Files with holes are stored like with `appendFile`.
    */
-  appendDirAll(name: string, src: string): void
+  appendDirAll(name: string, src: string, options?: AppendDirOptions | undefined | null): void
   /**
    * Append raw data to this archive with the specified name.
    *
@@ -141,6 +256,47 @@ export declare class Entry {
    * directly to ensure that various archive formats are handled correctly.
    */
   path(): string | null
+  /**
+   * Returns the link name for this entry, if any is found.
+   *
+   * Unlike `header().linkName()`, this takes GNU long link names and PAX
+   * `linkpath` records into account, so long link targets are complete.
+   */
+  linkName(): string | null
+  /**
+   * Returns the path in the archive this link entry points to, `null` if
+   * the entry is not a link.
+   *
+   * Symlink targets are relative to the directory of the entry, hard link
+   * targets to the root of the archive. `.` and `..` components are
+   * resolved, a target which leaves the root of the archive keeps its
+   * leading `..` components.
+   */
+  linkTarget(): string | null
+  /**
+   * Returns the starting position, in bytes, of the header of this entry in
+   * the decompressed archive.
+   *
+   * The header is always a contiguous section of 512 bytes. For entries with
+   * a long name or PAX records, this is the position of the last header,
+   * after the extension entries.
+   */
+  rawHeaderPosition(): bigint
+  /**
+   * Returns the starting position, in bytes, of the content of this entry in
+   * the decompressed archive.
+   */
+  rawFilePosition(): bigint
+  /**
+   * Returns the size, in bytes, the content of this entry takes in the
+   * archive: its stored size rounded up to a whole number of 512 bytes
+   * blocks.
+   *
+   * For old GNU sparse entries, this is the size of the stored data
+   * segments, without the extension blocks of the sparse map which may
+   * follow the header.
+   */
+  paddedSize(): bigint
   header(): ReadonlyHeader
   /**
    * Read the entirety of this entry into a byte vector.
@@ -152,6 +308,63 @@ export declare class Entry {
    * For large files, consider using streaming methods if memory usage is a concern.
    */
   asBytes(): Buffer
+  /**
+   * Returns the PAX extended header records which apply to this entry.
+   *
+   * This includes the records of the preceding PAX global headers, unless
+   * the entry has a record with the same key, followed by the records of the
+   * entry's own extended header.
+   *
+   * For an extended header entry itself, this returns its records and reads
+   * its whole content.
+   */
+  paxExtensions(): Array<PaxExtension>
+  /**
+   * Writes this entry to the specified `dst` path.
+   *
+   * The mask, permission, ownership, mtime, xattr and overwrite settings of
+   * the archive are applied. Unlike `unpackIn`, `dst` is used as is: it is
+   * not checked against path traversal and its parent directory must exist.
+   */
+  unpack(dst: string): void
+  /**
+   * Extracts this entry under the `dir` directory, at the location given by
+   * its path name.
+   *
+   * Entries whose path contains `..`, or would otherwise escape `dir`, are
+   * skipped. `dir` is created if missing. Returns whether the entry was
+   * written.
+   */
+  unpackIn(dir: string): boolean
+  /**
+   * Read the next chunk of this entry, at most `maxBytes` (64 KiB by
+   * default) long.
+   *
+   * Returns `null` once the whole content has been read.
+   */
+  read(maxBytes?: number | undefined | null): Buffer | null
+  /**
+   * Create a Node.js `Readable` over the content of this entry.
+   *
+   * Chunks are read on a worker thread as the stream is consumed, so
+   * large entries can be piped to a file or a hash without holding them in
+   * memory. The stream must be consumed before moving to the next entry.
+   *
+   * Requires Node.js 20.16 or later, for `process.getBuiltinModule`.
+   */
+  createReadStream(): import('node:stream').Readable
+}
+
+/**
+ * The content of an entry, in chunks read on a worker thread.
+ *
+ * This type implements JavaScript's async iterable protocol.
+ * It can be used with `for await...of` loops.
+ *
+ * @see https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Iteration_protocols#the_async_iterator_and_async_iterable_protocols
+ */
+export declare class EntryChunks {
+  [Symbol.asyncIterator](): AsyncGenerator<Buffer, void, undefined>
 }
 
 export declare class Header {
@@ -444,6 +657,90 @@ export declare class ReadonlyHeader {
   cksum(): number
 }
 
+export interface AppendDirOptions {
+  /**
+   * Called with the progress of the walk, at most once per
+   * `progressInterval` and once more when it completes.
+   */
+  onProgress?: (progress: Progress) => void
+  /**
+   * Minimum number of milliseconds between two `onProgress` calls, 100 by
+   * default.
+   */
+  progressInterval?: number
+}
+
+/**
+ * Resource limits of an archive, none by default.
+ *
+ * Exceeding one of them fails the current operation with an error whose
+ * `code` is `LimitExceeded` and whose message starts with `LimitExceeded:`.
+ * The async iterators of `entriesAsync` and `createReadStream` can only
+ * report it in the message.
+ */
+export interface ArchiveLimits {
+  /** Maximum number of bytes of the decompressed archive, headers included. */
+  maxTotalBytes?: number
+  /** Maximum size of the contents of a single entry. */
+  maxEntrySize?: number
+  /** Maximum number of entries. */
+  maxEntries?: number
+  /** Maximum length of the path of an entry, in bytes. */
+  maxPathLength?: number
+  /**
+   * Maximum ratio between the decompressed size of the archive and the
+   * number of input bytes read so far. Checked once more than 1 MiB has been
+   * decompressed.
+   */
+  maxCompressionRatio?: number
+}
+
+export interface ArchiveOptions {
+  /**
+   * Compression of the input, `auto` by default.
+   *
+   * Set it when the format can't be detected from the first bytes, such as
+   * for raw streams.
+   */
+  compression?: 'auto' | 'none' | 'gzip' | 'bzip2' | 'xz' | 'zstd' | 'lz4' | 'compress'
+  /** Resource limits, to safely read untrusted archives. */
+  limits?: ArchiveLimits
+}
+
+/** Compression of an archive. */
+export declare const enum Compression {
+  /** Detect the compression from the first bytes of the input. */
+  Auto = 'auto',
+  /** A plain tar archive. */
+  None = 'none',
+  Gzip = 'gzip',
+  Bzip2 = 'bzip2',
+  Xz = 'xz',
+  Zstd = 'zstd',
+  Lz4 = 'lz4',
+  /** The LZW format of the Unix `compress` utility (`.Z`). */
+  Compress = 'compress'
+}
+
+/** Metadata of an entry, as returned by `Archive.list`. */
+export interface EntryMetadata {
+  path: string
+  type: EntryType
+  /** Size of the content, for sparse files the size once expanded. */
+  size: bigint
+  /**
+   * `mode`, `uid`, `gid` and `mtime` are absent when the header field
+   * can't be parsed, such as when it was left empty.
+   */
+  mode?: number
+  uid?: bigint
+  gid?: bigint
+  mtime?: bigint
+  linkName?: string
+  uname?: string
+  gname?: string
+}
+
 /**
  * See [https://en.wikipedia.org/wiki/Tar_%28computing%29#UStar_format](https://en.wikipedia.org/wiki/Tar_%28computing%29#UStar_format)
  * Indicate for the type of file described by a header.
@@ -481,3 +778,246 @@ export declare const enum EntryType {
   /** Extended Header */
   XHeader = 12
 }
+
+export interface ExtractOptions {
+  /**
+   * Only read the entries matching one of these patterns, like the
+   * `include` option of `unpack`.
+   */
+  include?: Array<string>
+  /**
+   * Skip the entries matching one of these patterns, like the `exclude`
+   * option of `unpack`.
+   */
+  exclude?: Array<string>
+  /**
+   * Maximum number of bytes of all the file contents together. Exceeding it
+   * fails with a `LimitExceeded` error, like the limits of the archive.
+   */
+  maxTotalSize?: number
+}
+
+/** An entry read into memory by `Archive.toMap`. */
+export interface ExtractedEntry {
+  type: EntryType
+  /** The content of regular files, absent for directories and links. */
+  content?: Buffer
+  size: bigint
+  mode?: number
+  uid?: bigint
+  gid?: bigint
+  mtime?: bigint
+  linkName?: string
+  uname?: string
+  gname?: string
+}
+
+/**
+ * How `unpack` handles link entries. `contain` and `reject` fail on the
+ * first offending entry, with an error naming it.
+ */
+export declare const enum LinkPolicy {
+  /**
+   * Unpack links like `tar` does: hard links must point inside the
+   * destination, symlinks may point anywhere.
+   */
+  Allow = 'allow',
+  /**
+   * Reject symlinks with an absolute target, and symlinks or hard links
+   * whose target resolves outside of the destination. Entries are never
+   * written through a symlink which already exists in the destination.
+   */
+  Contain = 'contain',
+  /**
+   * Reject every link entry, and never write through a symlink which already
+   * exists in the destination.
+   */
+  Reject = 'reject'
+}
+
+export interface ListOptions {
+  /**
+   * Only list the entries matching one of these patterns, like the
+   * `include` option of `unpack`.
+   */
+  include?: Array<string>
+  /**
+   * Skip the entries matching one of these patterns, like the `exclude`
+   * option of `unpack`.
+   */
+  exclude?: Array<string>
+}
+
+/** A record of a PAX extended header. */
+export interface PaxExtension {
+  key: string
+  value: Buffer
+  /** `value` decoded as UTF-8, absent if it is not valid UTF-8. */
+  valueString?: string
+}
+
+/** Progress of a running `unpack`, `unpackAsync` or `appendDirAll`. */
+export interface Progress {
+  /** Number of entries written so far. */
+  entriesDone: number
+  /** Size of the contents of the entries written so far. */
+  bytesDone: bigint
+  /** Path of the last entry written. */
+  currentPath?: string
+  /**
+   * Number of bytes read from the input of the archive, before
+   * decompression. Not set when building an archive.
+   */
+  compressedBytesRead?: bigint
+}
+
+/** Why an entry was not unpacked. */
+export declare const enum SkipReason {
+  /**
+   * The path has a `..` component, or a hard link points outside of the
+   * destination.
+   */
+  PathTraversal = 'pathTraversal',
+  /** Character and block devices and named pipes are not unpacked. */
+  UnsupportedType = 'unsupportedType',
+  /** The destination already exists and `setOverwrite(false)` was called. */
+  ExistingFileKept = 'existingFileKept'
+}
+
+export interface SkippedEntry {
+  path: string
+  reason: 'pathTraversal' | 'unsupportedType' | 'existingFileKept'
+}
+
+export interface UnpackOptions {
+  /**
+   * Only unpack the entries matching one of these patterns.
+   *
+   * Patterns follow the `.gitignore` syntax and are matched against the
+   * entry path stored in the archive, with any leading `/` or `./` removed,
+   * before `stripComponents` and `rename` are applied. A pattern matching a
+   * directory selects everything below it, and `!pattern` deselects entries
+   * again. Parent directories of the selected entries are created as needed.
+   */
+  include?: Array<string>
+  /**
+   * Skip the entries matching one of these patterns, even if they are
+   * selected by `include`. Uses the same syntax as `include`.
+   */
+  exclude?: Array<string>
+  /**
+   * Remove this many leading components from the path of every entry, like
+   * `tar --strip-components`. Entries with fewer components are skipped.
+   */
+  stripComponents?: number
+  /**
+   * Rewrite the path of every entry, after `stripComponents` is applied.
+   * Return `null` to skip the entry. The target of hard links is rewritten
+   * the same way.
+   *
+   * Rewritten paths go through the same checks as the original ones: paths
+   * containing `..` are skipped, and nothing is written outside of the
+   * destination.
+   */
+  rename?: (path: string) => string | null | undefined
+  /**
+   * How symlinks and hard links are handled, `allow` by default. See
+   * `LinkPolicy`.
+   */
+  links?: 'allow' | 'contain' | 'reject'
+  /**
+   * Called with the progress of the extraction, at most once per
+   * `progressInterval` and once more when it completes.
+   *
+   * `unpackAsync` queues the calls without waiting for them, so the progress
+   * is not held back by the JavaScript thread.
+   */
+  onProgress?: (progress: Progress) => void
+  /**
+   * Minimum number of milliseconds between two `onProgress` calls, 100 by
+   * default.
+   */
+  progressInterval?: number
+  /**
+   * Cancel the extraction. The signal is checked before each entry is
+   * written, so entries which are already on disk are kept.
+   *
+   * Only honoured by `unpackAsync`, which rejects right away when the
+   * signal is already aborted.
+   */
+  signal?: AbortSignal
+}
+
+/** Outcome of `unpack` and `unpackAsync`. */
+export interface UnpackReport {
+  /**
+   * Paths of the written entries, relative to the destination and after
+   * `stripComponents` and `rename` are applied.
+   */
+  written: Array<string>
+  /**
+   * Entries which were rejected, with their path in the archive.
+   *
+   * Entries left out by `include`, `exclude`, `stripComponents` or `rename`
+   * are not rejections, they are not listed.
+   */
+  skipped: Array<SkippedEntry>
+  entriesWritten: number
+  /** Size of the contents of the written entries. */
+  bytesWritten: bigint
+  entriesSkipped: number
+}
+
+export interface VerifyProblem {
+  kind: 'checksumMismatch' | 'invalidHeader' | 'truncated' | 'missingEndMarker' | 'trailingData' | 'corruptCompression'
+  /**
+   * Offset in the decompressed archive of the header, or of the data, the
+   * problem was found at.
+   */
+  offset: bigint
+  /** Path of the entry the problem affects, if any. */
+  path?: string
+  message: string
+}
+
+/** A problem found by `Archive.verify`. */
+export declare const enum VerifyProblemKind {
+  /**
+   * The checksum of a header does not match its content. The following
+   * blocks are skipped until a valid header is found.
+   */
+  ChecksumMismatch = 'checksumMismatch',
+  /**
+   * A field of a header, such as its size, can't be parsed. The following
+   * blocks are skipped until a valid header is found.
+   */
+  InvalidHeader = 'invalidHeader',
+  /**
+   * The archive ends in the middle of a header or of the content of an
+   * entry.
+   */
+  Truncated = 'truncated',
+  /** The archive ends without the two zero blocks marking its end. */
+  MissingEndMarker = 'missingEndMarker',
+  /** Non-zero data follows the end-of-archive marker. */
+  TrailingData = 'trailingData',
+  /**
+   * The compressed stream is corrupt, for example its checksum does not
+   * match the decompressed data.
+   */
+  CorruptCompression = 'corruptCompression'
+}
+
+/** Result of `Archive.verify`. */
+export interface VerifyReport {
+  /** Whether no problem was found. */
+  ok: boolean
+  /** Number of entries read, PAX and GNU extension headers excluded. */
+  entries: number
+  /**
+   * Size of the decompressed archive, end-of-archive marker and padding
+   * included.
+   */
+  bytesRead: bigint
+  problems: Array<VerifyProblem>
+}
@@ -509,9 +509,15 @@ if (!nativeBinding) {
 
 module.exports = nativeBinding
 module.exports.Archive = nativeBinding.Archive
+module.exports.AsyncEntries = nativeBinding.AsyncEntries
 module.exports.Builder = nativeBinding.Builder
 module.exports.Entries = nativeBinding.Entries
 module.exports.Entry = nativeBinding.Entry
+module.exports.EntryChunks = nativeBinding.EntryChunks
 module.exports.Header = nativeBinding.Header
 module.exports.ReadonlyHeader = nativeBinding.ReadonlyHeader
+module.exports.Compression = nativeBinding.Compression
 module.exports.EntryType = nativeBinding.EntryType
+module.exports.LinkPolicy = nativeBinding.LinkPolicy
+module.exports.SkipReason = nativeBinding.SkipReason
+module.exports.VerifyProblemKind = nativeBinding.VerifyProblemKind
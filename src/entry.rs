@@ -1,40 +1,525 @@
-use std::io::Read;
+use std::{
+  borrow::Cow,
+  future::Future,
+  io::{self, Read},
+  path::{Component, Path},
+  sync::{
+    Arc, Mutex,
+    atomic::{AtomicBool, Ordering},
+  },
+};
 
 use napi::{
-  bindgen_prelude::{Env, Reference, SharedReference},
-  iterator::Generator,
+  bindgen_prelude::{
+    AsyncGenerator, Buffer, Env, FnArgs, Function, JsObjectValue, Object, Reference,
+    SharedReference, WeakReference, spawn_blocking,
+  },
+  iterator::ScopedGenerator,
+  tokio::sync::oneshot,
 };
 use napi_derive::napi;
 
-use crate::header::ReadonlyHeader;
+use crate::header::{EntryType, ReadonlyHeader};
+use crate::limits::{LIMIT_EXCEEDED, Limits};
+use crate::sparse::SparseEntry;
+use crate::unpack::{Content, Selection, UnpackSettings, prepare_dst, unpack_at};
 use crate::{Archive, ArchiveSource};
 
+/// A PAX extended header record.
+type PaxRecord = (Vec<u8>, Vec<u8>);
+
+/// State of the iteration over the entries of an archive.
+struct EntriesState {
+  /// Where the next entry starts, to tell which entry could not be read.
+  index: u32,
+  offset: u64,
+  /// Records of the PAX global headers read so far, they apply to all the
+  /// entries which follow.
+  pax_globals: Arc<Vec<PaxRecord>>,
+  limits: Arc<Limits>,
+  /// The settings of the archive when the iteration started.
+  settings: UnpackSettings,
+  busy: Busy,
+  archive: ArchiveRef,
+}
+
+impl EntriesState {
+  fn new(archive: ArchiveRef, limits: Arc<Limits>, settings: UnpackSettings, busy: Busy) -> Self {
+    Self {
+      index: 0,
+      offset: 0,
+      pax_globals: Arc::default(),
+      archive,
+      limits,
+      settings,
+      busy,
+    }
+  }
+
+  /// Reads the next entry, keeping track of its position.
+  ///
+  /// Errors end the iteration. They are returned unless `lenient` is set, in
+  /// which case the archive is treated as if it ended there. Exceeding a
+  /// limit is always an error.
+  fn next_entry(
+    &mut self,
+    entries: &mut tar::Entries<'static, ArchiveSource>,
+    lenient: bool,
+  ) -> napi::Result<Option<Entry>> {
+    match entries.next().map(|entry| self.read_pax_globals(entry?)) {
+      None => Ok(None),
+      Some(Ok(entry)) => {
+        let entry = Entry::new(entry, self).map_err(|err| self.error(err))?;
+        self
+          .limits
+          .check_entry(self.index + 1, &entry.file_path()?, entry.size())?;
+        self.index += 1;
+        self.offset = entry.inner.raw_file_position() + entry.padded_stored_size();
+        Ok(Some(entry))
+      }
+      Some(Err(_)) if lenient && !self.limits.is_exceeded() => Ok(None),
+      Some(Err(err)) => Err(self.error(err)),
+    }
+  }
+
+  /// Merges the records of a PAX global header into `pax_globals`. A record
+  /// with an empty value removes the key.
+  fn read_pax_globals(
+    &mut self,
+    mut entry: tar::Entry<'static, ArchiveSource>,
+  ) -> io::Result<tar::Entry<'static, ArchiveSource>> {
+    if entry.header().entry_type() != tar::EntryType::XGlobalHeader {
+      return Ok(entry);
+    }
+    let mut globals = self.pax_globals.as_ref().clone();
+    for record in entry.pax_extensions()?.into_iter().flatten() {
+      let record = record?;
+      globals.retain(|(key, _)| key != record.key_bytes());
+      if !record.value_bytes().is_empty() {
+        globals.push((record.key_bytes().to_vec(), record.value_bytes().to_vec()));
+      }
+    }
+    self.pax_globals = Arc::new(globals);
+    Ok(entry)
+  }
+
+  fn error(&self, err: io::Error) -> napi::Error {
+    self.limits.exceeded_or(napi::Error::new(
+      napi::Status::GenericFailure,
+      format!(
+        "Failed to read entry #{} at byte offset {}: {err}",
+        self.index, self.offset
+      ),
+    ))
+  }
+}
+
+/// Reads the metadata of all the remaining entries.
+pub(crate) fn list(
+  mut entries: SharedReference<Archive, tar::Entries<'static, ArchiveSource>>,
+  archive: ArchiveRef,
+  lenient: bool,
+  limits: Arc<Limits>,
+  settings: UnpackSettings,
+  busy: Busy,
+  options: &ListOptions,
+) -> napi::Result<Vec<EntryMetadata>> {
+  let selection = Selection::new(options.include.as_deref(), options.exclude.as_deref())?;
+  let mut state = EntriesState::new(archive, limits, settings, busy);
+  let mut list = Vec::new();
+  while let Some(entry) = state.next_entry(&mut entries, lenient)? {
+    let metadata = entry.metadata()?;
+    let is_dir = matches!(metadata.entry_type, EntryType::Directory);
+    if selection.is_selected(Path::new(&metadata.path), is_dir) {
+      list.push(metadata);
+    }
+  }
+  Ok(list)
+}
+
+/// Reads the remaining entries into memory, see `Archive.toMap`.
+pub(crate) fn extract(
+  mut entries: SharedReference<Archive, tar::Entries<'static, ArchiveSource>>,
+  archive: ArchiveRef,
+  lenient: bool,
+  limits: Arc<Limits>,
+  settings: UnpackSettings,
+  busy: Busy,
+  options: &ExtractOptions,
+) -> napi::Result<Vec<(String, ExtractedEntry)>> {
+  let selection = Selection::new(options.include.as_deref(), options.exclude.as_deref())?;
+  let max_total_size = options.max_total_size.map(|max| max.max(0) as u64);
+  let mut total_size = 0u64;
+  let mut state = EntriesState::new(archive, limits, settings, busy);
+  let mut extracted = Vec::new();
+  while let Some(mut entry) = state.next_entry(&mut entries, lenient)? {
+    let metadata = entry.metadata()?;
+    let is_file = match metadata.entry_type {
+      EntryType::Regular | EntryType::Continuous | EntryType::GNUSparse => true,
+      EntryType::Directory | EntryType::Symlink | EntryType::Link => false,
+      _ => continue,
+    };
+    if !selection.is_selected(Path::new(&metadata.path), !is_file) {
+      continue;
+    }
+    let content = if is_file {
+      total_size = total_size.saturating_add(metadata.size);
+      if let Some(max) = max_total_size.filter(|max| total_size > *max) {
+        return Err(state.limits.exceeded(format!(
+          "the files of the archive are larger than maxTotalSize ({max})"
+        )));
+      }
+      // The size comes from the header, which may lie, so only part of it is
+      // reserved upfront.
+      let mut content = Vec::with_capacity(metadata.size.min(MAX_RESERVED) as usize);
+      EntryContent(&mut entry)
+        .read_to_end(&mut content)
+        .map_err(|err| entry.limits.exceeded_or(err.into()))?;
+      Some(content.into())
+    } else {
+      None
+    };
+    extracted.push((
+      metadata.path.clone(),
+      ExtractedEntry::new(metadata, content),
+    ));
+  }
+  Ok(extracted)
+}
+
 #[napi(iterator)]
 pub struct Entries {
-  pub(crate) inner: SharedReference<Archive, tar::Entries<'static, ArchiveSource>>,
+  inner: SharedReference<Archive, tar::Entries<'static, ArchiveSource>>,
+  state: EntriesState,
+  lenient: bool,
+}
+
+impl Entries {
+  pub(crate) fn new(
+    inner: SharedReference<Archive, tar::Entries<'static, ArchiveSource>>,
+    archive: ArchiveRef,
+    lenient: bool,
+    limits: Arc<Limits>,
+    settings: UnpackSettings,
+    busy: Busy,
+  ) -> Self {
+    Self {
+      inner,
+      state: EntriesState::new(archive, limits, settings, busy),
+      lenient,
+    }
+  }
 }
 
 #[napi]
-impl Generator for Entries {
+impl<'env> ScopedGenerator<'env> for Entries {
   type Yield = Entry;
   type Next = ();
   type Return = ();
 
-  fn next(&mut self, _next: Option<()>) -> Option<Self::Yield> {
-    let entry = self.inner.next()?.map(crate::entry::Entry::new).ok()?;
-    Some(entry)
+  fn next(&mut self, env: &'env Env, _next: Option<()>) -> Option<Self::Yield> {
+    let next = self
+      .state
+      .busy
+      .check()
+      .and_then(|()| self.state.next_entry(&mut self.inner, self.lenient));
+    match next {
+      Ok(entry) => entry,
+      Err(err) => {
+        // `next()` throws the pending exception instead of returning.
+        let code = match self.state.limits.is_violation(&err) {
+          true => LIMIT_EXCEEDED,
+          false => err.status.as_ref(),
+        };
+        let _ = env.throw_error(&err.reason, Some(code));
+        None
+      }
+    }
+  }
+}
+
+#[napi(async_iterator)]
+pub struct AsyncEntries {
+  inner: SharedReference<Archive, tar::Entries<'static, ArchiveSource>>,
+  state: Arc<Mutex<EntriesState>>,
+  lenient: bool,
+  busy: Busy,
+  env: Env,
+}
+
+impl AsyncEntries {
+  pub(crate) fn new(
+    env: Env,
+    inner: SharedReference<Archive, tar::Entries<'static, ArchiveSource>>,
+    archive: ArchiveRef,
+    lenient: bool,
+    limits: Arc<Limits>,
+    settings: UnpackSettings,
+    busy: Busy,
+  ) -> Self {
+    Self {
+      inner,
+      state: Arc::new(Mutex::new(EntriesState::new(
+        archive,
+        limits,
+        settings,
+        busy.clone(),
+      ))),
+      lenient,
+      busy,
+      env,
+    }
+  }
+}
+
+/// Hands the archive state over to the blocking thread pool.
+///
+/// `tar` entries borrow the archive through `Cell`s and `RefCell`s, so they
+/// are not `Send`. The worker only runs while it holds the `Busy` flag of the
+/// archive, and every method of `Archive`, `Entries` and `Entry` checks that
+/// flag first, so the archive is never accessed from two threads at once.
+///
+/// Also carries the references `keep_alive` holds, which are only dropped on
+/// the JS thread.
+struct Handoff<T>(T);
+
+unsafe impl<T> Send for Handoff<T> {}
+
+/// Set while a worker thread holds an archive, see `Handoff`. Shared by the
+/// archive and everything reading it.
+#[derive(Clone, Default)]
+pub(crate) struct Busy(Arc<AtomicBool>);
+
+impl Busy {
+  /// Fails if a worker thread holds the archive.
+  pub(crate) fn check(&self) -> napi::Result<()> {
+    if self.0.load(Ordering::Acquire) {
+      return Err(busy_error());
+    }
+    Ok(())
+  }
+}
+
+fn busy_error() -> napi::Error {
+  napi::Error::new(
+    napi::Status::GenericFailure,
+    "The archive is in use by a pending asynchronous call, await it first",
+  )
+}
+
+/// Runs `task` on the blocking thread pool, holding `busy` until it settles,
+/// unless another task already holds it.
+///
+/// `owners` are references to the JS objects whose memory `task` uses. They
+/// are held until it settles, so that dropping these objects while it runs
+/// does not free that memory under it.
+fn spawn_exclusive<O: 'static, T: Send + 'static>(
+  env: &Env,
+  busy: &Busy,
+  owners: napi::Result<O>,
+  task: impl FnOnce() -> napi::Result<T> + Send + 'static,
+) -> impl Future<Output = napi::Result<T>> + Send + 'static {
+  let settled = owners.and_then(|owners| keep_alive(env, owners));
+  let busy = busy.0.clone();
+  let acquired = settled.is_ok() && !busy.swap(true, Ordering::AcqRel);
+  async move {
+    let _settled = settled?;
+    if !acquired {
+      return Err(busy_error());
+    }
+    let result = spawn_blocking(task).await;
+    busy.store(false, Ordering::Release);
+    result.map_err(|err| napi::Error::from_reason(err.to_string()))?
+  }
+}
+
+/// Holds `owners` until the returned sender is dropped, on any thread, then
+/// drops them on the JS thread, the only one napi references can be released
+/// from.
+fn keep_alive<O: 'static>(env: &Env, owners: O) -> napi::Result<oneshot::Sender<()>> {
+  let (settled, released) = oneshot::channel::<()>();
+  let owners = Handoff(owners);
+  env.spawn_future_with_callback(
+    async move {
+      let _ = released.await;
+      Ok(owners)
+    },
+    |_, owners| {
+      drop(owners);
+      Ok(())
+    },
+  )?;
+  Ok(settled)
+}
+
+#[napi]
+impl AsyncGenerator for AsyncEntries {
+  type Yield = Entry;
+  type Next = ();
+  type Return = ();
+
+  fn next(
+    &mut self,
+    _next: Option<()>,
+  ) -> impl Future<Output = napi::Result<Option<Self::Yield>>> + Send + 'static {
+    let entries = Handoff(&mut *self.inner as *mut tar::Entries<'static, ArchiveSource>);
+    // The `Entries` are freed along with the archive, not with this iterator.
+    let archive = self.inner.clone_owner(self.env);
+    let state = self.state.clone();
+    let lenient = self.lenient;
+    spawn_exclusive(&self.env, &self.busy, archive, move || {
+      let entries = entries;
+      // SAFETY: see `Handoff`, the `Entries` are kept alive by the archive
+      // reference `spawn_exclusive` holds.
+      let entries = unsafe { &mut *entries.0 };
+      let mut state = state.lock().unwrap_or_else(|err| err.into_inner());
+      state.next_entry(entries, lenient)
+    })
   }
 }
 
 #[napi]
 pub struct Entry {
   inner: tar::Entry<'static, ArchiveSource>,
+  pax_globals: Arc<Vec<PaxRecord>>,
+  /// Set for the sparse entries which `tar` does not expand.
+  sparse: Option<SparseEntry>,
+  limits: Arc<Limits>,
+  settings: UnpackSettings,
+  busy: Busy,
+  archive: ArchiveRef,
+}
+
+/// A weak reference to the archive, which the entries borrow from, see
+/// `EntryChunks`.
+#[derive(Clone)]
+pub(crate) struct ArchiveRef(WeakReference<Archive>);
+
+impl ArchiveRef {
+  pub(crate) fn new(archive: &Reference<Archive>) -> Self {
+    Self(archive.downgrade())
+  }
+}
+
+// SAFETY: it is only upgraded on the JS thread. Cloning or dropping it, as
+// `AsyncEntries` does on the blocking thread pool, only updates atomic counts.
+unsafe impl Send for ArchiveRef {}
+
+#[napi(object, object_from_js = false)]
+/// Metadata of an entry, as returned by `Archive.list`.
+pub struct EntryMetadata {
+  pub path: String,
+  #[napi(js_name = "type")]
+  pub entry_type: EntryType,
+  /// Size of the content, for sparse files the size once expanded.
+  pub size: u64,
+  /// `mode`, `uid`, `gid` and `mtime` are absent when the header field
+  /// can't be parsed, such as when it was left empty.
+  pub mode: Option<u32>,
+  pub uid: Option<u64>,
+  pub gid: Option<u64>,
+  pub mtime: Option<u64>,
+  pub link_name: Option<String>,
+  pub uname: Option<String>,
+  pub gname: Option<String>,
+}
+
+#[napi(object, object_to_js = false)]
+#[derive(Default)]
+pub struct ListOptions {
+  /// Only list the entries matching one of these patterns, like the
+  /// `include` option of `unpack`.
+  pub include: Option<Vec<String>>,
+  /// Skip the entries matching one of these patterns, like the `exclude`
+  /// option of `unpack`.
+  pub exclude: Option<Vec<String>>,
+}
+
+#[napi(object, object_to_js = false)]
+#[derive(Default)]
+pub struct ExtractOptions {
+  /// Only read the entries matching one of these patterns, like the
+  /// `include` option of `unpack`.
+  pub include: Option<Vec<String>>,
+  /// Skip the entries matching one of these patterns, like the `exclude`
+  /// option of `unpack`.
+  pub exclude: Option<Vec<String>>,
+  /// Maximum number of bytes of all the file contents together. Exceeding it
+  /// fails with a `LimitExceeded` error, like the limits of the archive.
+  pub max_total_size: Option<i64>,
+}
+
+#[napi(object, object_from_js = false)]
+/// An entry read into memory by `Archive.toMap`.
+pub struct ExtractedEntry {
+  #[napi(js_name = "type")]
+  pub entry_type: EntryType,
+  /// The content of regular files, absent for directories and links.
+  pub content: Option<Buffer>,
+  pub size: u64,
+  pub mode: Option<u32>,
+  pub uid: Option<u64>,
+  pub gid: Option<u64>,
+  pub mtime: Option<u64>,
+  pub link_name: Option<String>,
+  pub uname: Option<String>,
+  pub gname: Option<String>,
+}
+
+impl ExtractedEntry {
+  fn new(metadata: EntryMetadata, content: Option<Buffer>) -> Self {
+    Self {
+      entry_type: metadata.entry_type,
+      content,
+      size: metadata.size,
+      mode: metadata.mode,
+      uid: metadata.uid,
+      gid: metadata.gid,
+      mtime: metadata.mtime,
+      link_name: metadata.link_name,
+      uname: metadata.uname,
+      gname: metadata.gname,
+    }
+  }
+}
+
+#[napi(object, object_from_js = false)]
+/// A record of a PAX extended header.
+pub struct PaxExtension {
+  pub key: String,
+  pub value: Buffer,
+  /// `value` decoded as UTF-8, absent if it is not valid UTF-8.
+  pub value_string: Option<String>,
+}
+
+impl PaxExtension {
+  fn new(key: &[u8], value: &[u8]) -> Self {
+    Self {
+      key: String::from_utf8_lossy(key).into_owned(),
+      value: value.to_vec().into(),
+      value_string: std::str::from_utf8(value).ok().map(str::to_owned),
+    }
+  }
 }
 
+// SAFETY: an `Entry` only crosses threads when it is produced by
+// `AsyncEntries` or read by `EntryChunks`, on a worker holding the `Busy`
+// flag of its archive, see `Handoff`. `spawn_exclusive` keeps the archive,
+// and the `Entry` read by `EntryChunks`, alive until that worker is done.
+unsafe impl Send for Entry {}
+
 #[napi]
 impl Entry {
-  pub fn new(inner: tar::Entry<'static, ArchiveSource>) -> Self {
-    Self { inner }
+  fn new(mut inner: tar::Entry<'static, ArchiveSource>, state: &EntriesState) -> io::Result<Self> {
+    Ok(Self {
+      sparse: SparseEntry::from_entry(&mut inner)?,
+      inner,
+      pax_globals: state.pax_globals.clone(),
+      limits: state.limits.clone(),
+      settings: state.settings,
+      busy: state.busy.clone(),
+      archive: state.archive.clone(),
+    })
   }
 
   #[napi]
@@ -51,11 +536,83 @@ impl Entry {
   /// It is recommended to use this method instead of inspecting the `header`
   /// directly to ensure that various archive formats are handled correctly.
   pub fn path(&self) -> napi::Result<Option<String>> {
-    Ok(self.inner.path()?.to_str().map(|s| s.to_owned()))
+    self.busy.check()?;
+    Ok(self.file_path()?.to_str().map(|s| s.to_owned()))
+  }
+
+  #[napi]
+  /// Returns the link name for this entry, if any is found.
+  ///
+  /// Unlike `header().linkName()`, this takes GNU long link names and PAX
+  /// `linkpath` records into account, so long link targets are complete.
+  pub fn link_name(&self) -> napi::Result<Option<String>> {
+    self.busy.check()?;
+    Ok(
+      self
+        .inner
+        .link_name()?
+        .map(|link| link.to_string_lossy().into_owned()),
+    )
+  }
+
+  #[napi]
+  /// Returns the path in the archive this link entry points to, `null` if
+  /// the entry is not a link.
+  ///
+  /// Symlink targets are relative to the directory of the entry, hard link
+  /// targets to the root of the archive. `.` and `..` components are
+  /// resolved, a target which leaves the root of the archive keeps its
+  /// leading `..` components.
+  pub fn link_target(&self) -> napi::Result<Option<String>> {
+    self.busy.check()?;
+    let Some(link) = self.inner.link_name()? else {
+      return Ok(None);
+    };
+    let target = if self.inner.header().entry_type().is_symlink() && link.is_relative() {
+      let path = self.inner.path()?;
+      path.parent().unwrap_or(Path::new("")).join(link)
+    } else {
+      link.into_owned()
+    };
+    Ok(Some(normalize_archive_path(&target)))
+  }
+
+  #[napi]
+  /// Returns the starting position, in bytes, of the header of this entry in
+  /// the decompressed archive.
+  ///
+  /// The header is always a contiguous section of 512 bytes. For entries with
+  /// a long name or PAX records, this is the position of the last header,
+  /// after the extension entries.
+  pub fn raw_header_position(&self) -> napi::Result<u64> {
+    self.busy.check()?;
+    Ok(self.inner.raw_header_position())
+  }
+
+  #[napi]
+  /// Returns the starting position, in bytes, of the content of this entry in
+  /// the decompressed archive.
+  pub fn raw_file_position(&self) -> napi::Result<u64> {
+    self.busy.check()?;
+    Ok(self.inner.raw_file_position())
+  }
+
+  #[napi]
+  /// Returns the size, in bytes, the content of this entry takes in the
+  /// archive: its stored size rounded up to a whole number of 512 bytes
+  /// blocks.
+  ///
+  /// For old GNU sparse entries, this is the size of the stored data
+  /// segments, without the extension blocks of the sparse map which may
+  /// follow the header.
+  pub fn padded_size(&self) -> napi::Result<u64> {
+    self.busy.check()?;
+    Ok(self.padded_stored_size())
   }
 
   #[napi]
   pub fn header(&self, this: Reference<Entry>, env: Env) -> napi::Result<ReadonlyHeader> {
+    self.busy.check()?;
     Ok(ReadonlyHeader::new(
       this.share_with(env, |e| Ok(e.inner.header()))?,
     ))
@@ -69,9 +626,288 @@ impl Entry {
   ///
   /// This method will read the entire contents of this entry into memory.
   /// For large files, consider using streaming methods if memory usage is a concern.
-  pub fn as_bytes(&mut self) -> napi::Result<napi::bindgen_prelude::Buffer> {
+  pub fn as_bytes(&mut self, env: Env) -> napi::Result<napi::bindgen_prelude::Buffer> {
+    self.busy.check()?;
     let mut data = Vec::new();
-    self.inner.read_to_end(&mut data)?;
+    EntryContent(self)
+      .read_to_end(&mut data)
+      .map_err(|err| self.limits.with_code(&env, err.into()))?;
     Ok(data.into())
   }
+
+  #[napi]
+  /// Returns the PAX extended header records which apply to this entry.
+  ///
+  /// This includes the records of the preceding PAX global headers, unless
+  /// the entry has a record with the same key, followed by the records of the
+  /// entry's own extended header.
+  ///
+  /// For an extended header entry itself, this returns its records and reads
+  /// its whole content.
+  pub fn pax_extensions(&mut self, env: Env) -> napi::Result<Vec<PaxExtension>> {
+    self.busy.check()?;
+    let mut local = self
+      .local_pax_extensions()
+      .map_err(|err| self.limits.with_code(&env, err.into()))?;
+    let mut records = self
+      .pax_globals
+      .iter()
+      .filter(|(key, _)| !local.iter().any(|record| record.key.as_bytes() == key))
+      .map(|(key, value)| PaxExtension::new(key, value))
+      .collect::<Vec<_>>();
+    records.append(&mut local);
+    Ok(records)
+  }
+
+  #[napi]
+  /// Writes this entry to the specified `dst` path.
+  ///
+  /// The mask, permission, ownership, mtime, xattr and overwrite settings of
+  /// the archive are applied. Unlike `unpackIn`, `dst` is used as is: it is
+  /// not checked against path traversal and its parent directory must exist.
+  pub fn unpack(&mut self, env: Env, dst: String) -> napi::Result<()> {
+    self.busy.check()?;
+    let result = match &mut self.sparse {
+      Some(sparse) => sparse.unpack(&mut self.inner, Path::new(&dst), self.settings),
+      None => self.inner.unpack(dst).map(drop),
+    };
+    result.map_err(|err| {
+      self
+        .limits
+        .with_code(&env, self.limits.exceeded_or(err.into()))
+    })
+  }
+
+  #[napi]
+  /// Extracts this entry under the `dir` directory, at the location given by
+  /// its path name.
+  ///
+  /// Entries whose path contains `..`, or would otherwise escape `dir`, are
+  /// skipped. `dir` is created if missing. Returns whether the entry was
+  /// written.
+  pub fn unpack_in(&mut self, env: Env, dir: String) -> napi::Result<bool> {
+    self.busy.check()?;
+    let dir = prepare_dst(Path::new(&dir))?;
+    let path = self.file_path()?.into_owned();
+    let result = match &mut self.sparse {
+      Some(sparse) => unpack_at(
+        &mut self.inner,
+        &dir,
+        &path,
+        Content::Sparse(sparse, self.settings),
+      ),
+      None => self.inner.unpack_in(dir),
+    };
+    result.map_err(|err| {
+      self
+        .limits
+        .with_code(&env, self.limits.exceeded_or(err.into()))
+    })
+  }
+
+  #[napi]
+  /// Read the next chunk of this entry, at most `maxBytes` (64 KiB by
+  /// default) long.
+  ///
+  /// Returns `null` once the whole content has been read.
+  pub fn read(&mut self, env: Env, max_bytes: Option<u32>) -> napi::Result<Option<Buffer>> {
+    self.busy.check()?;
+    let chunk = self
+      .read_chunk(max_bytes.unwrap_or(CHUNK_SIZE))
+      .map_err(|err| self.limits.with_code(&env, err.into()))?;
+    Ok(chunk.map(Buffer::from))
+  }
+
+  #[napi(ts_return_type = "import('node:stream').Readable")]
+  /// Create a Node.js `Readable` over the content of this entry.
+  ///
+  /// Chunks are read on a worker thread as the stream is consumed, so
+  /// large entries can be piped to a file or a hash without holding them in
+  /// memory. The stream must be consumed before moving to the next entry.
+  ///
+  /// Requires Node.js 20.16 or later, for `process.getBuiltinModule`.
+  pub fn create_read_stream<'env>(
+    &self,
+    this: Reference<Entry>,
+    env: &'env Env,
+  ) -> napi::Result<Object<'env>> {
+    self.busy.check()?;
+    let process = env.get_global()?.get_named_property::<Object>("process")?;
+    let get_builtin_module = process
+      .get_named_property::<Option<Function<&str, Object>>>("getBuiltinModule")?
+      .ok_or_else(|| {
+        napi::Error::new(
+          napi::Status::GenericFailure,
+          "`createReadStream()` requires `process.getBuiltinModule`, available since Node.js 20.16",
+        )
+      })?;
+    let readable = get_builtin_module
+      .apply(process, "node:stream")?
+      .get_named_property::<Function>("Readable")?;
+    let archive = self.archive.0.upgrade(*env)?.ok_or_else(|| {
+      napi::Error::new(
+        napi::Status::GenericFailure,
+        "The archive of this entry was garbage collected",
+      )
+    })?;
+    let mut options = Object::new(env)?;
+    options.set("objectMode", false)?;
+    let chunks = EntryChunks {
+      entry: this,
+      archive,
+      busy: self.busy.clone(),
+      env: *env,
+    };
+    readable
+      .get_named_property::<Function<FnArgs<(EntryChunks, Object)>, Object>>("from")?
+      .apply(readable, (chunks, options).into())
+  }
+
+  fn metadata(&self) -> napi::Result<EntryMetadata> {
+    let header = self.inner.header();
+    let size = if header.entry_type().is_gnu_sparse() {
+      header.size()?
+    } else {
+      self.size()
+    };
+    Ok(EntryMetadata {
+      path: self.file_path()?.to_string_lossy().into_owned(),
+      entry_type: header.entry_type().into(),
+      size,
+      mode: header.mode().ok(),
+      uid: header.uid().ok(),
+      gid: header.gid().ok(),
+      mtime: header.mtime().ok(),
+      link_name: self.link_name()?,
+      uname: header
+        .username_bytes()
+        .map(|name| String::from_utf8_lossy(name).into_owned()),
+      gname: header
+        .groupname_bytes()
+        .map(|name| String::from_utf8_lossy(name).into_owned()),
+    })
+  }
+
+  /// The records of the entry's own extended header.
+  fn local_pax_extensions(&mut self) -> io::Result<Vec<PaxExtension>> {
+    let mut records = Vec::new();
+    for record in self.inner.pax_extensions()?.into_iter().flatten() {
+      let record = record?;
+      records.push(PaxExtension::new(record.key_bytes(), record.value_bytes()));
+    }
+    Ok(records)
+  }
+
+  fn read_chunk(&mut self, max_bytes: u32) -> io::Result<Option<Vec<u8>>> {
+    let mut chunk = Vec::new();
+    EntryContent(self)
+      .take(u64::from(max_bytes))
+      .read_to_end(&mut chunk)?;
+    Ok((!chunk.is_empty()).then_some(chunk))
+  }
+
+  /// The path of the file of this entry, which differs from the path of the
+  /// entry for PAX sparse files.
+  fn file_path(&self) -> io::Result<Cow<'_, Path>> {
+    match self.sparse.as_ref().and_then(SparseEntry::name) {
+      Some(name) => Ok(Cow::Borrowed(Path::new(name))),
+      None => self.inner.path(),
+    }
+  }
+
+  /// The size of the content in the archive, padded to 512 bytes. `tar`
+  /// reports the expanded size of old GNU sparse entries, not the stored one.
+  fn padded_stored_size(&self) -> u64 {
+    let header = self.inner.header();
+    let size = if header.entry_type().is_gnu_sparse() {
+      header.entry_size().unwrap_or(self.inner.size())
+    } else {
+      self.inner.size()
+    };
+    size.next_multiple_of(512)
+  }
+
+  /// The size of the content, holes of PAX sparse files included.
+  fn size(&self) -> u64 {
+    self
+      .sparse
+      .as_ref()
+      .map_or(self.inner.size(), SparseEntry::size)
+  }
+}
+
+/// Reads the content of an entry, with the holes of PAX sparse files.
+struct EntryContent<'a>(&'a mut Entry);
+
+impl Read for EntryContent<'_> {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    let entry = &mut *self.0;
+    match &mut entry.sparse {
+      Some(sparse) => sparse.read(&mut entry.inner, buf),
+      None => entry.inner.read(buf),
+    }
+  }
+}
+
+const CHUNK_SIZE: u32 = 64 * 1024;
+
+/// Maximum number of bytes reserved for the content of an entry before
+/// reading it.
+const MAX_RESERVED: u64 = 1024 * 1024;
+
+/// Lexically resolves `.` and `..` in a path of the archive, joined with `/`.
+pub(crate) fn normalize_archive_path(path: &Path) -> String {
+  let mut absolute = false;
+  let mut parts: Vec<Cow<'_, str>> = Vec::new();
+  for component in path.components() {
+    match component {
+      Component::Prefix(_) | Component::RootDir => absolute = true,
+      Component::CurDir => {}
+      Component::ParentDir => match parts.last() {
+        Some(last) if last != ".." => {
+          parts.pop();
+        }
+        // The parent of the root is the root itself.
+        _ if absolute => {}
+        _ => parts.push(Cow::Borrowed("..")),
+      },
+      Component::Normal(part) => parts.push(part.to_string_lossy()),
+    }
+  }
+  let path = parts.join("/");
+  if absolute { format!("/{path}") } else { path }
+}
+
+#[napi(async_iterator)]
+/// The content of an entry, in chunks read on a worker thread.
+pub struct EntryChunks {
+  entry: Reference<Entry>,
+  archive: Reference<Archive>,
+  busy: Busy,
+  env: Env,
+}
+
+#[napi]
+impl AsyncGenerator for EntryChunks {
+  type Yield = Buffer;
+  type Next = ();
+  type Return = ();
+
+  fn next(
+    &mut self,
+    _next: Option<()>,
+  ) -> impl Future<Output = napi::Result<Option<Self::Yield>>> + Send + 'static {
+    let entry = Handoff(&mut *self.entry as *mut Entry);
+    let owners = self
+      .entry
+      .clone(self.env)
+      .and_then(|entry| Ok((entry, self.archive.clone(self.env)?)));
+    spawn_exclusive(&self.env, &self.busy, owners, move || {
+      let entry = entry;
+      // SAFETY: see `Handoff`, the `Entry` and the archive it reads from are
+      // kept alive by the references `spawn_exclusive` holds.
+      let chunk = unsafe { &mut *entry.0 }.read_chunk(CHUNK_SIZE)?;
+      Ok(chunk.map(Buffer::from))
+    })
+  }
 }
@@ -2,33 +2,239 @@
 
 use std::{
   fs::File,
-  io::{BufReader, Cursor, Read, Write},
+  io::{BufRead, BufReader, Cursor, Read, Seek, Write},
+  path::Path,
+  sync::{
+    Arc,
+    atomic::{AtomicU64, Ordering},
+  },
 };
 
-use napi::bindgen_prelude::{Either, Either4, Env, Reference};
+use napi::{
+  Task,
+  bindgen_prelude::{
+    Either3, Env, FnArgs, Function, JsObjectValue, JsValue, Object, PromiseRaw, Reference,
+    Uint8Array, Unknown, spawn_blocking,
+  },
+};
 use napi_derive::napi;
 
-use crate::entry::Entries;
+use crate::entry::{
+  ArchiveRef, AsyncEntries, Busy, Entries, EntryMetadata, ExtractOptions, ExtractedEntry,
+  ListOptions, extract, list,
+};
+use crate::limits::{ArchiveLimits, Limits};
+use crate::lzw::LzwDecoder;
+use crate::progress::{OnProgress, OnProgressCallback, ProgressReporter, threadsafe_on_progress};
+use crate::stream::ChunkReader;
+use crate::unpack::{
+  Rename, UnpackHooks, UnpackOptions, UnpackReport, UnpackSettings, UnpackSignal, UnpackTask,
+  Unpacker, aborted_error, threadsafe_rename,
+};
+use crate::verify::{VerifyReport, VerifyTask, verify};
 
 mod entry;
 mod header;
+mod limits;
+mod lzw;
+mod progress;
+mod sparse;
+mod stream;
+mod unpack;
+mod verify;
 
 #[cfg(all(not(target_family = "wasm"), not(target_arch = "x86")))]
 #[global_allocator]
 static GLOBAL: mimalloc_safe::MiMalloc = mimalloc_safe::MiMalloc;
 
+#[napi(string_enum = "lowercase")]
+/// Compression of an archive.
+pub enum Compression {
+  /// Detect the compression from the first bytes of the input.
+  Auto,
+  /// A plain tar archive.
+  None,
+  Gzip,
+  Bzip2,
+  Xz,
+  Zstd,
+  Lz4,
+  /// The LZW format of the Unix `compress` utility (`.Z`).
+  Compress,
+}
+
+impl Compression {
+  /// Detects the compression from the first 512 bytes of an archive, `None`
+  /// if the format is not supported.
+  ///
+  /// Tar files older than POSIX.1-1988 have no `ustar` magic, they are
+  /// recognised by the checksum of their first header instead.
+  fn detect(head: &[u8]) -> Option<Self> {
+    match infer::get(head).map(|s| s.extension()) {
+      Some("tar") => Some(Self::None),
+      Some("gz") => Some(Self::Gzip),
+      Some("bz2") => Some(Self::Bzip2),
+      Some("xz") => Some(Self::Xz),
+      Some("zst") => Some(Self::Zstd),
+      Some("lz4") => Some(Self::Lz4),
+      Some("Z") => Some(Self::Compress),
+      Some(_) => None,
+      None => has_tar_checksum(head).then_some(Self::None),
+    }
+  }
+}
+
+/// Validates the checksum of a tar header, which is computed with the
+/// checksum field itself filled with spaces.
+fn has_tar_checksum(head: &[u8]) -> bool {
+  let Some(block) = head.get(..512) else {
+    return false;
+  };
+  let Ok(checksum) = tar::Header::from_byte_slice(block).cksum() else {
+    return false;
+  };
+  let sum = block[..148]
+    .iter()
+    .chain(&block[156..])
+    .fold(8 * u32::from(b' '), |sum, byte| sum + u32::from(*byte));
+  checksum == sum
+}
+
+#[napi(object, object_to_js = false)]
+pub struct ArchiveOptions {
+  /// Compression of the input, `auto` by default.
+  ///
+  /// Set it when the format can't be detected from the first bytes, such as
+  /// for raw streams.
+  #[napi(ts_type = "'auto' | 'none' | 'gzip' | 'bzip2' | 'xz' | 'zstd' | 'lz4' | 'compress'")]
+  pub compression: Option<Compression>,
+  /// Resource limits, to safely read untrusted archives.
+  pub limits: Option<ArchiveLimits>,
+}
+
 pub struct ArchiveSource {
-  inner: Either4<
-    File,
-    Cursor<Vec<u8>>,
-    flate2::read::GzDecoder<FileOrBuffer>,
-    bzip2::read::BzDecoder<FileOrBuffer>,
-  >,
+  inner: Decoder,
+  /// Number of bytes read from the input, before decompression.
+  bytes_read: Arc<AtomicU64>,
+  /// Number of bytes read from the decoder.
+  decompressed: u64,
+  limits: Arc<Limits>,
+}
+
+enum Decoder {
+  Tar(Input),
+  Gz(flate2::read::MultiGzDecoder<Input>),
+  Bz2(bzip2::read::MultiBzDecoder<Input>),
+  Xz(Box<lzma_rust2::XzReader<BufReader<Input>>>),
+  Zstd(zstd::stream::read::Decoder<'static, BufReader<Input>>),
+  Lz4(MultiLz4Decoder),
+  Lzw(LzwDecoder<BufReader<Input>>),
+  /// A stream whose format is only known once its first bytes arrived.
+  Pending(Box<ChunkReader>),
+}
+
+impl Decoder {
+  /// Creates the decoder for `compression`, which must not be `Auto`.
+  ///
+  /// Decoders keep going after the end of a gzip member or a bzip2, xz, zstd
+  /// or lz4 stream, so that concatenated files are decoded in full.
+  fn new(compression: Compression, input: Input) -> std::io::Result<Self> {
+    Ok(match compression {
+      Compression::Auto => unreachable!("compression must be detected first"),
+      Compression::None => Self::Tar(input),
+      Compression::Gzip => Self::Gz(flate2::read::MultiGzDecoder::new(input)),
+      Compression::Bzip2 => Self::Bz2(bzip2::read::MultiBzDecoder::new(input)),
+      Compression::Xz => Self::Xz(Box::new(lzma_rust2::XzReader::new(
+        BufReader::new(input),
+        true,
+      ))),
+      Compression::Zstd => Self::Zstd(zstd::stream::read::Decoder::new(input)?),
+      Compression::Lz4 => Self::Lz4(MultiLz4Decoder(lz4_flex::frame::FrameDecoder::new(
+        BufReader::new(input),
+      ))),
+      Compression::Compress => Self::Lzw(LzwDecoder::new(BufReader::new(input))),
+    })
+  }
+
+  /// Detects the format of a stream from its first bytes.
+  fn from_stream(mut stream: ChunkReader, bytes_read: Arc<AtomicU64>) -> std::io::Result<Self> {
+    let compression = Compression::detect(stream.peek(512)?).ok_or_else(|| {
+      std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        "Unsupported file type for input stream",
+      )
+    })?;
+    Self::new(
+      compression,
+      Input::new(FileOrBuffer::Stream(stream), bytes_read),
+    )
+  }
+}
+
+/// Decodes concatenated lz4 frames, `FrameDecoder` stops at the end of each.
+struct MultiLz4Decoder(lz4_flex::frame::FrameDecoder<BufReader<Input>>);
+
+impl MultiLz4Decoder {
+  /// Whether another frame follows the one which just ended.
+  fn has_next_frame(&mut self) -> std::io::Result<bool> {
+    let head = self.0.get_mut().fill_buf()?;
+    Ok(match head.get(..4) {
+      Some(magic) => matches!(
+        u32::from_le_bytes([magic[0], magic[1], magic[2], magic[3]]),
+        // Frames, skippable frames and legacy frames.
+        0x184D2204 | 0x184D2A50..=0x184D2A5F | 0x184C2102
+      ),
+      // Let the decoder report the truncated magic number.
+      None => !head.is_empty(),
+    })
+  }
+}
+
+impl Read for MultiLz4Decoder {
+  fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+    loop {
+      let len = self.0.read(buf)?;
+      if len > 0 || buf.is_empty() || !self.has_next_frame()? {
+        return Ok(len);
+      }
+    }
+  }
+}
+
+/// The raw input of an archive, counting the bytes read from it.
+struct Input {
+  source: FileOrBuffer,
+  bytes_read: Arc<AtomicU64>,
+}
+
+impl Input {
+  fn new(source: FileOrBuffer, bytes_read: Arc<AtomicU64>) -> Self {
+    Self { source, bytes_read }
+  }
+
+  /// An empty input, standing in for one which has been moved out.
+  fn empty() -> Self {
+    Self::new(
+      FileOrBuffer::Buffer(Cursor::new(Uint8Array::from(Vec::new()))),
+      Arc::default(),
+    )
+  }
+}
+
+impl Read for Input {
+  fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+    let len = self.source.read(buf)?;
+    self.bytes_read.fetch_add(len as u64, Ordering::Relaxed);
+    Ok(len)
+  }
 }
 
 enum FileOrBuffer {
   File(File),
-  Buffer(Cursor<Vec<u8>>),
+  /// Borrows the bytes of the JavaScript buffer, which the `Uint8Array`
+  /// keeps alive, instead of copying them.
+  Buffer(Cursor<Uint8Array>),
+  Stream(ChunkReader),
 }
 
 impl Read for FileOrBuffer {
@@ -36,109 +242,338 @@ impl Read for FileOrBuffer {
     match self {
       Self::File(file) => file.read(buf),
       Self::Buffer(buffer) => buffer.read(buf),
+      Self::Stream(stream) => stream.read(buf),
     }
   }
 }
 
 impl ArchiveSource {
-  fn from_node_input(input: Either<String, &[u8]>) -> Result<Self, napi::Error> {
-    match input {
-      Either::A(path) => match infer::get_from_path(&path)?.map(|s| s.extension()) {
-        Some("tar") => {
-          let file = File::open(&path)?;
-          Ok(Self {
-            inner: Either4::A(file),
-          })
-        }
-        Some("bz2") => {
-          let file = File::open(&path)?;
-          let bz2 = bzip2::read::BzDecoder::new(FileOrBuffer::File(file));
-          Ok(Self {
-            inner: Either4::D(bz2),
-          })
-        }
-        Some("xz") => {
-          let mut file = BufReader::new(File::open(&path)?);
-          let mut output = Vec::new();
-          lzma_rs::xz_decompress(&mut file, &mut output).map_err(anyhow::Error::from)?;
-          Ok(Self {
-            inner: Either4::B(Cursor::new(output)),
-          })
-        }
-        Some("gz") => {
-          let file = File::open(&path)?;
-          Ok(Self {
-            inner: Either4::C(flate2::read::GzDecoder::new(FileOrBuffer::File(file))),
-          })
-        }
-        _ => Err(napi::Error::new(
-          napi::Status::InvalidArg,
-          format!("Unsupported file type for {path}"),
-        )),
-      },
-      Either::B(buffer) => match infer::get(buffer).map(|s| s.extension()) {
-        Some("tar") => Ok(Self {
-          inner: Either4::B(Cursor::new(buffer.to_vec())),
-        }),
-        Some("bz2") => {
-          let bz2 = bzip2::read::BzDecoder::new(FileOrBuffer::Buffer(Cursor::new(buffer.to_vec())));
-          Ok(Self {
-            inner: Either4::D(bz2),
-          })
-        }
-        Some("xz") => {
-          let mut input = buffer;
-          let mut output = Vec::new();
-          lzma_rs::xz_decompress(&mut input, &mut output).map_err(anyhow::Error::from)?;
-          Ok(Self {
-            inner: Either4::B(Cursor::new(output)),
-          })
+  fn from_node_input(
+    env: &Env,
+    input: Either3<String, Uint8Array, Object>,
+    compression: Compression,
+    limits: Arc<Limits>,
+  ) -> napi::Result<Self> {
+    let bytes_read = Arc::new(AtomicU64::new(0));
+    let counted = |source| Input::new(source, bytes_read.clone());
+    let inner = match input {
+      Either3::A(path) => {
+        let mut file = File::open(&path)?;
+        let compression = match compression {
+          Compression::Auto => {
+            let mut head = Vec::with_capacity(512);
+            (&mut file).take(512).read_to_end(&mut head)?;
+            file.rewind()?;
+            Compression::detect(&head).ok_or_else(|| {
+              napi::Error::new(
+                napi::Status::InvalidArg,
+                format!("Unsupported file type for {path}"),
+              )
+            })?
+          }
+          compression => compression,
+        };
+        Decoder::new(compression, counted(FileOrBuffer::File(file)))?
+      }
+      Either3::B(buffer) => {
+        let compression = match compression {
+          Compression::Auto => Compression::detect(&buffer).ok_or_else(|| {
+            napi::Error::new(napi::Status::InvalidArg, "Unsupported file type for input ")
+          })?,
+          compression => compression,
+        };
+        Decoder::new(
+          compression,
+          counted(FileOrBuffer::Buffer(Cursor::new(buffer))),
+        )?
+      }
+      Either3::C(iterable) => {
+        let stream = ChunkReader::new(env, iterable)?;
+        match compression {
+          Compression::Auto => Decoder::Pending(Box::new(stream)),
+          compression => Decoder::new(compression, counted(FileOrBuffer::Stream(stream)))?,
         }
-        Some("gz") => Ok(Self {
-          inner: Either4::C(flate2::read::GzDecoder::new(FileOrBuffer::Buffer(
-            Cursor::new(buffer.to_vec()),
-          ))),
-        }),
-        _ => Err(napi::Error::new(
-          napi::Status::InvalidArg,
-          "Unsupported file type for input ",
-        )),
-      },
+      }
+    };
+    Ok(Self {
+      inner,
+      bytes_read,
+      decompressed: 0,
+      limits,
+    })
+  }
+
+  /// Whether the archive is decompressed while it is read.
+  fn is_compressed(&self) -> bool {
+    !matches!(self.inner, Decoder::Tar(_))
+  }
+
+  /// A source without data, left behind by `unpackAsync` and `verify`.
+  fn empty() -> Self {
+    Self {
+      inner: Decoder::Tar(Input::empty()),
+      bytes_read: Arc::default(),
+      decompressed: 0,
+      limits: Arc::default(),
     }
   }
 }
 
 impl Read for ArchiveSource {
   fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-    match &mut self.inner {
-      Either4::A(file) => file.read(buf),
-      Either4::B(buffer) => buffer.read(buf),
-      Either4::C(gz) => gz.read(buf),
-      Either4::D(bz2) => bz2.read(buf),
+    if let Decoder::Pending(_) = self.inner {
+      let Decoder::Pending(stream) =
+        std::mem::replace(&mut self.inner, Decoder::Tar(Input::empty()))
+      else {
+        unreachable!()
+      };
+      self.inner = Decoder::from_stream(*stream, self.bytes_read.clone())?;
     }
+    let len = match &mut self.inner {
+      Decoder::Tar(tar) => tar.read(buf),
+      Decoder::Gz(gz) => gz.read(buf),
+      Decoder::Bz2(bz2) => bz2.read(buf),
+      Decoder::Xz(xz) => xz.read(buf),
+      Decoder::Zstd(zstd) => zstd.read(buf),
+      Decoder::Lz4(lz4) => lz4.read(buf),
+      Decoder::Lzw(lzw) => lzw.read(buf),
+      Decoder::Pending(_) => unreachable!(),
+    }?;
+    self.decompressed += len as u64;
+    self
+      .limits
+      .check_read(self.decompressed, self.bytes_read.load(Ordering::Relaxed))?;
+    Ok(len)
   }
 }
 
 #[napi]
 pub struct Archive {
   inner: tar::Archive<ArchiveSource>,
+  /// Streams can only be pulled from a worker thread.
+  streaming: bool,
+  lenient: bool,
+  settings: UnpackSettings,
+  /// Shared with the `ArchiveSource`, to report the progress of `unpack`.
+  bytes_read: Arc<AtomicU64>,
+  /// Shared with the `ArchiveSource`, which enforces the limits on the size
+  /// of the archive.
+  limits: Arc<Limits>,
+  /// Shared with the entries, see `Busy`.
+  busy: Busy,
 }
 
 #[napi]
 impl Archive {
-  #[napi(constructor)]
+  #[napi(
+    constructor,
+    ts_args_type = "input: string | Uint8Array | AsyncIterable<Uint8Array>, options?: ArchiveOptions"
+  )]
   /// Create a new archive with the underlying path.
-  pub fn new(input: Either<String, &[u8]>) -> napi::Result<Self> {
+  ///
+  /// A `Buffer` input is read in place rather than copied, so it must not be
+  /// modified while the archive is in use.
+  ///
+  /// The input can also be an async iterable of chunks, such as a Node.js
+  /// `Readable`. Such an archive is read lazily, one chunk at a time, and only
+  /// supports `entriesAsync` and `unpackAsync`.
+  ///
+  /// Set `limits` in `options` when reading untrusted archives.
+  pub fn new(
+    env: Env,
+    input: Either3<String, Uint8Array, Object>,
+    options: Option<ArchiveOptions>,
+  ) -> napi::Result<Self> {
+    let streaming = matches!(input, Either3::C(_));
+    let (compression, limits) = options
+      .map(|options| (options.compression, options.limits))
+      .unwrap_or_default();
+    let limits = Arc::new(limits.map(Limits::new).unwrap_or_default());
+    let source = ArchiveSource::from_node_input(
+      &env,
+      input,
+      compression.unwrap_or(Compression::Auto),
+      limits.clone(),
+    )?;
     Ok(Self {
-      inner: tar::Archive::new(ArchiveSource::from_node_input(input)?),
+      bytes_read: source.bytes_read.clone(),
+      inner: tar::Archive::new(source),
+      streaming,
+      lenient: false,
+      settings: UnpackSettings::default(),
+      limits,
+      busy: Busy::default(),
     })
   }
 
+  fn unpacker(&self, options: &UnpackOptions) -> napi::Result<Unpacker> {
+    Unpacker::new(
+      options,
+      self.settings,
+      self.bytes_read.clone(),
+      self.limits.clone(),
+    )
+  }
+
+  fn ensure_not_streaming(&self, method: &str) -> napi::Result<()> {
+    if self.streaming {
+      return Err(napi::Error::new(
+        napi::Status::GenericFailure,
+        format!(
+          "`{method}()` is not supported for archives created from a stream, use `entriesAsync()`, `unpackAsync()` or `verifyAsync()`"
+        ),
+      ));
+    }
+    Ok(())
+  }
+
   #[napi]
   pub fn entries(&mut self, this: Reference<Archive>, env: Env) -> napi::Result<Entries> {
+    self.busy.check()?;
+    self.ensure_not_streaming("entries")?;
+    let archive = ArchiveRef::new(&this);
+    let entries = this.share_with(env, |archive| Ok(archive.inner.entries()?))?;
+
+    Ok(Entries::new(
+      entries,
+      archive,
+      self.lenient,
+      self.limits.clone(),
+      self.settings,
+      self.busy.clone(),
+    ))
+  }
+
+  #[napi]
+  /// Returns the metadata of all the entries of this archive.
+  ///
+  /// The archive is walked in a single call, which is much cheaper than
+  /// calling `path()` and `header()` on each entry of `entries()`. Entry
+  /// contents are skipped.
+  ///
+  /// Use the `include` and `exclude` options to only list some entries.
+  pub fn list(
+    &mut self,
+    this: Reference<Archive>,
+    env: Env,
+    options: Option<ListOptions>,
+  ) -> napi::Result<Vec<EntryMetadata>> {
+    self.busy.check()?;
+    self.ensure_not_streaming("list")?;
+    let archive = ArchiveRef::new(&this);
+    let entries = this.share_with(env, |archive| Ok(archive.inner.entries()?))?;
+
+    list(
+      entries,
+      archive,
+      self.lenient,
+      self.limits.clone(),
+      self.settings,
+      self.busy.clone(),
+      &options.unwrap_or_default(),
+    )
+    .map_err(|err| self.limits.with_code(&env, err))
+  }
+
+  #[napi(ts_return_type = "Map<string, ExtractedEntry>")]
+  /// Reads the whole archive into memory, as a `Map` from entry paths to
+  /// their content and metadata.
+  ///
+  /// Regular files come with their `content`. Directories, symlinks and hard
+  /// links are metadata-only records, and other entry types are left out. A
+  /// path occurring more than once maps to its last entry, as if the archive
+  /// was unpacked.
+  ///
+  /// Nothing is written to disk, which makes this usable where `unpack` is
+  /// not, such as in the WebAssembly build. Use `include`, `exclude` and
+  /// `maxTotalSize` in `options` to bound the memory used.
+  pub fn to_map<'env>(
+    &mut self,
+    this: Reference<Archive>,
+    env: &'env Env,
+    options: Option<ExtractOptions>,
+  ) -> napi::Result<Object<'env>> {
+    let map = env
+      .get_global()?
+      .get_named_property::<Function<(), Object>>("Map")?
+      .new_instance(())?
+      .coerce_to_object()?;
+    let set =
+      map.get_named_property::<Function<FnArgs<(String, ExtractedEntry)>, Unknown>>("set")?;
+    for (path, entry) in self.extract(this, env, "toMap", options)? {
+      set.apply(map, (path, entry).into())?;
+    }
+    Ok(map)
+  }
+
+  #[napi(ts_return_type = "Record<string, ExtractedEntry>")]
+  /// Reads the whole archive into memory like `toMap`, as a plain object
+  /// keyed by entry paths.
+  pub fn extract_to_object<'env>(
+    &mut self,
+    this: Reference<Archive>,
+    env: &'env Env,
+    options: Option<ExtractOptions>,
+  ) -> napi::Result<Object<'env>> {
+    let mut object = Object::new(env)?;
+    for (path, entry) in self.extract(this, env, "extractToObject", options)? {
+      object.set(path, entry)?;
+    }
+    Ok(object)
+  }
+
+  fn extract(
+    &mut self,
+    this: Reference<Archive>,
+    env: &Env,
+    method: &str,
+    options: Option<ExtractOptions>,
+  ) -> napi::Result<Vec<(String, ExtractedEntry)>> {
+    self.busy.check()?;
+    self.ensure_not_streaming(method)?;
+    let archive = ArchiveRef::new(&this);
+    let entries = this.share_with(*env, |archive| Ok(archive.inner.entries()?))?;
+
+    extract(
+      entries,
+      archive,
+      self.lenient,
+      self.limits.clone(),
+      self.settings,
+      self.busy.clone(),
+      &options.unwrap_or_default(),
+    )
+    .map_err(|err| self.limits.with_code(env, err))
+  }
+
+  #[napi]
+  /// Returns an async iterator over the entries of this archive, to be used
+  /// with `for await`.
+  ///
+  /// Reading and decompressing each header happens on a worker thread, so
+  /// listing a large compressed archive does not block the event loop.
+  ///
+  /// Entries must be processed in order: read the content of an entry before
+  /// requesting the next one. While a `next()` call is pending, the other
+  /// methods of the archive and of its entries throw.
+  pub fn entries_async(
+    &mut self,
+    this: Reference<Archive>,
+    env: Env,
+  ) -> napi::Result<AsyncEntries> {
+    self.busy.check()?;
+    let archive = ArchiveRef::new(&this);
     let entries = this.share_with(env, |archive| Ok(archive.inner.entries()?))?;
 
-    Ok(Entries { inner: entries })
+    Ok(AsyncEntries::new(
+      env,
+      entries,
+      archive,
+      self.lenient,
+      self.limits.clone(),
+      self.settings,
+      self.busy.clone(),
+    ))
   }
 
   #[napi]
@@ -151,9 +586,120 @@ impl Archive {
   /// This operation is relatively sensitive in that it will not write files
   /// outside of the path specified by `dst`. Files in the archive which have
   /// a '..' in their path are skipped during the unpacking process.
-  pub fn unpack(&mut self, to: String) -> napi::Result<()> {
-    self.inner.unpack(to)?;
-    Ok(())
+  ///
+  /// Use the `include` and `exclude` options to only unpack some entries, and
+  /// `stripComponents` or `rename` to unpack them under another path.
+  ///
+  /// Returns the list of written entries, and of the entries which were
+  /// rejected, such as those with a '..' in their path.
+  pub fn unpack(
+    &mut self,
+    env: &Env,
+    to: String,
+    options: Option<UnpackOptions>,
+  ) -> napi::Result<UnpackReport> {
+    self.busy.check()?;
+    self.ensure_not_streaming("unpack")?;
+    let options = options.unwrap_or_default();
+    let rename = options
+      .rename
+      .as_ref()
+      .map(|rename| rename.borrow_back(env))
+      .transpose()?
+      .map(|rename| move |path| rename.call(path));
+    let on_progress = options
+      .on_progress
+      .as_ref()
+      .map(|on_progress| on_progress.borrow_back(env))
+      .transpose()?
+      .map(|on_progress| move |progress| on_progress.call(progress));
+    let report = self.unpacker(&options)?.unpack(
+      &mut self.inner,
+      Path::new(&to),
+      UnpackHooks {
+        rename: rename.as_ref().map(|rename| rename as Rename),
+        on_progress: on_progress
+          .as_ref()
+          .map(|on_progress| on_progress as OnProgress),
+      },
+    );
+    report.map_err(|err| self.limits.with_code(env, err))
+  }
+
+  #[napi(ts_return_type = "Promise<UnpackReport>")]
+  /// Unpacks the contents tarball into the specified `dst` on a worker
+  /// thread.
+  ///
+  /// This behaves like `unpack`, but decompression and file writes happen off
+  /// the JavaScript main thread. Pass an `AbortSignal` in `options` to stop
+  /// the extraction between two entries; the returned promise then rejects
+  /// with an error reporting how many entries were written before the abort,
+  /// none if it was aborted before the extraction started.
+  ///
+  /// The archive is consumed by this call, it yields no entries afterwards.
+  pub fn unpack_async<'env>(
+    &mut self,
+    env: &'env Env,
+    to: String,
+    options: Option<UnpackOptions>,
+  ) -> napi::Result<PromiseRaw<'env, UnpackReport>> {
+    self.busy.check()?;
+    let options = options.unwrap_or_default();
+    if options
+      .signal
+      .as_ref()
+      .is_some_and(UnpackSignal::is_aborted)
+    {
+      return env.spawn_future(async { Err(aborted_error(0)) });
+    }
+    let unpacker = self.unpacker(&options)?;
+    let rename = options
+      .rename
+      .as_ref()
+      .map(|rename| threadsafe_rename(env, rename))
+      .transpose()?;
+    let on_progress = options
+      .on_progress
+      .as_ref()
+      .map(|on_progress| threadsafe_on_progress(env, on_progress))
+      .transpose()?;
+    // The signal is not handed to napi, which would reject with a bare
+    // `AbortError` when aborted before the task starts: `Unpacker` checks it
+    // before each entry, the first one included.
+    let task = UnpackTask::new(self.take(), to.into(), unpacker, rename, on_progress);
+    spawn_task(env, task)
+  }
+
+  #[napi]
+  /// Reads the whole archive to check its integrity, without extracting it.
+  ///
+  /// Every header and the content of every entry are read to the end. Header
+  /// checksums, truncation, a missing end-of-archive marker, data after it
+  /// and errors of the compressed stream, such as checksum failures, are
+  /// reported with their offset in the decompressed archive instead of being
+  /// thrown. Only errors reading the input and exceeded limits are thrown.
+  ///
+  /// The archive is consumed by this call, so verify it before reading any
+  /// entry.
+  pub fn verify(&mut self, env: Env) -> napi::Result<VerifyReport> {
+    self.busy.check()?;
+    self.ensure_not_streaming("verify")?;
+    verify(&mut self.take().into_inner()).map_err(|err| self.limits.with_code(&env, err))
+  }
+
+  #[napi(ts_return_type = "Promise<VerifyReport>")]
+  /// Checks the integrity of the archive like `verify`, on a worker thread.
+  pub fn verify_async<'env>(
+    &mut self,
+    env: &'env Env,
+  ) -> napi::Result<PromiseRaw<'env, VerifyReport>> {
+    self.busy.check()?;
+    spawn_task(env, VerifyTask::new(self.take().into_inner()))
+  }
+
+  /// Takes the archive out, leaving an empty one behind.
+  fn take(&mut self) -> tar::Archive<ArchiveSource> {
+    std::mem::replace(&mut self.inner, tar::Archive::new(ArchiveSource::empty()))
   }
 
   #[napi]
@@ -167,8 +713,11 @@ impl Archive {
   /// ```
   ///
   /// The mask is 0 by default and is currently only implemented on Unix.
-  pub fn set_mask(&mut self, mask: u32) {
+  pub fn set_mask(&mut self, mask: u32) -> napi::Result<()> {
+    self.busy.check()?;
+    self.settings.mask = mask;
     self.inner.set_mask(mask);
+    Ok(())
   }
 
   #[napi]
@@ -179,8 +728,11 @@ impl Archive {
   /// Unix using xattr support. This may eventually be implemented for
   /// Windows, however, if other archive implementations are found which do
   /// this as well.
-  pub fn set_unpack_xattrs(&mut self, unpack_xattrs: bool) {
+  pub fn set_unpack_xattrs(&mut self, unpack_xattrs: bool) -> napi::Result<()> {
+    self.busy.check()?;
+    self.settings.unpack_xattrs = unpack_xattrs;
     self.inner.set_unpack_xattrs(unpack_xattrs);
+    Ok(())
   }
 
   #[napi]
@@ -189,8 +741,11 @@ impl Archive {
   ///
   /// This flag is disabled by default and is currently only implemented on
   /// Unix.
-  pub fn set_preserve_permissions(&mut self, preserve_permissions: bool) {
+  pub fn set_preserve_permissions(&mut self, preserve_permissions: bool) -> napi::Result<()> {
+    self.busy.check()?;
+    self.settings.preserve_permissions = preserve_permissions;
     self.inner.set_preserve_permissions(preserve_permissions);
+    Ok(())
   }
 
   #[napi]
@@ -199,14 +754,23 @@ impl Archive {
   ///
   /// This flag is disabled by default and is currently only implemented on
   /// Unix.
-  pub fn set_preserve_ownerships(&mut self, preserve_ownerships: bool) {
+  pub fn set_preserve_ownerships(&mut self, preserve_ownerships: bool) -> napi::Result<()> {
+    self.busy.check()?;
+    self.settings.preserve_ownerships = preserve_ownerships;
     self.inner.set_preserve_ownerships(preserve_ownerships);
+    Ok(())
   }
 
   #[napi]
   /// Indicate whether files and symlinks should be overwritten on extraction.
-  pub fn set_overwrite(&mut self, overwrite: bool) {
+  ///
+  /// When disabled, entries whose destination exists are skipped by `unpack`
+  /// and listed in its report.
+  pub fn set_overwrite(&mut self, overwrite: bool) -> napi::Result<()> {
+    self.busy.check()?;
+    self.settings.overwrite = overwrite;
     self.inner.set_overwrite(overwrite);
+    Ok(())
   }
 
   #[napi]
@@ -214,17 +778,36 @@ impl Archive {
   /// this entry.
   ///
   /// This flag is enabled by default.
-  pub fn set_preserve_mtime(&mut self, preserve_mtime: bool) {
+  pub fn set_preserve_mtime(&mut self, preserve_mtime: bool) -> napi::Result<()> {
+    self.busy.check()?;
+    self.settings.preserve_mtime = preserve_mtime;
     self.inner.set_preserve_mtime(preserve_mtime);
+    Ok(())
   }
 
   #[napi]
   /// Ignore zeroed headers, which would otherwise indicate to the archive that it has no more
   /// entries.
   ///
-  /// This can be used in case multiple tar archives have been concatenated together.
-  pub fn set_ignore_zeros(&mut self, ignore_zeros: bool) {
+  /// This can be used in case multiple tar archives have been concatenated together,
+  /// compressed or not (e.g. `cat a.tar.gz b.tar.gz`).
+  pub fn set_ignore_zeros(&mut self, ignore_zeros: bool) -> napi::Result<()> {
+    self.busy.check()?;
     self.inner.set_ignore_zeros(ignore_zeros);
+    Ok(())
+  }
+
+  #[napi]
+  /// Indicate whether `entries` and `entriesAsync` silently stop at an entry
+  /// which can't be read, such as a corrupted header or a truncated stream.
+  ///
+  /// This flag is disabled by default: the iteration throws an error naming
+  /// the index of the entry and its byte offset in the uncompressed archive
+  /// instead.
+  pub fn set_lenient(&mut self, lenient: bool) -> napi::Result<()> {
+    self.busy.check()?;
+    self.lenient = lenient;
+    Ok(())
   }
 }
 
@@ -249,6 +832,18 @@ impl Write for BuilderOutput {
   }
 }
 
+#[napi(object, object_to_js = false)]
+#[derive(Default)]
+pub struct AppendDirOptions {
+  /// Called with the progress of the walk, at most once per
+  /// `progressInterval` and once more when it completes.
+  #[napi(ts_type = "(progress: Progress) => void")]
+  pub on_progress: Option<OnProgressCallback>,
+  /// Minimum number of milliseconds between two `onProgress` calls, 100 by
+  /// default.
+  pub progress_interval: Option<u32>,
+}
+
 #[napi]
 pub struct Builder {
   inner: tar::Builder<BuilderOutput>,
@@ -265,9 +860,11 @@ impl Builder {
       None => BuilderOutput::Buffer(Cursor::new(Vec::new())),
     };
 
-    Ok(Self {
-      inner: tar::Builder::new(builder_output),
-    })
+    let mut inner = tar::Builder::new(builder_output);
+    // Files with holes are appended as PAX sparse entries by `sparse`, rather
+    // than as the old GNU sparse entries `tar` writes.
+    inner.sparse(false);
+    Ok(Self { inner })
   }
 
   #[napi]
@@ -276,9 +873,13 @@ impl Builder {
   /// This function will open the file specified by `src` and add it to the
   /// archive as `name`. The `name` specified is the name that will be used
   /// inside the archive.
+  ///
+  /// On Linux, Android and FreeBSD, files with holes are detected with
+  /// `SEEK_DATA` and `SEEK_HOLE`, and stored as PAX 1.0 sparse entries which
+  /// only hold their data.
   pub fn append_file(&mut self, name: String, src: String) -> napi::Result<()> {
     let mut file = File::open(src)?;
-    self.inner.append_file(name, &mut file)?;
+    sparse::append_file(&mut self.inner, Path::new(&name), &mut file)?;
     Ok(())
   }
 
@@ -287,10 +888,34 @@ impl Builder {
   ///
   /// This function will recursively add all files and directories in the
   /// specified `src` directory to the archive, preserving their relative
-  /// paths under `name`.
-  pub fn append_dir_all(&mut self, name: String, src: String) -> napi::Result<()> {
-    self.inner.append_dir_all(name, src)?;
-    Ok(())
+  /// paths under `name`. Files with holes are stored like with `appendFile`.
+  pub fn append_dir_all(
+    &mut self,
+    env: &Env,
+    name: String,
+    src: String,
+    options: Option<AppendDirOptions>,
+  ) -> napi::Result<()> {
+    let options = options.unwrap_or_default();
+    let on_progress = options
+      .on_progress
+      .as_ref()
+      .map(|on_progress| on_progress.borrow_back(env))
+      .transpose()?
+      .map(|on_progress| move |progress| on_progress.call(progress));
+    let mut progress = ProgressReporter::new(
+      on_progress
+        .as_ref()
+        .map(|on_progress| on_progress as OnProgress),
+      options.progress_interval,
+    );
+    append_dir_all(
+      &mut self.inner,
+      Path::new(&name),
+      Path::new(&src),
+      &mut progress,
+    )?;
+    progress.finish()
   }
 
   #[napi]
@@ -332,3 +957,69 @@ impl Builder {
     }
   }
 }
+
+/// Runs `task` like an `AsyncTask`, but on the blocking thread pool of the
+/// async runtime rather than on the libuv one.
+///
+/// Reading an archive created from a stream waits for its chunks, which
+/// `fs.createReadStream` reads on the libuv thread pool: tasks occupying all
+/// of its threads would wait forever.
+fn spawn_task<T: Task + 'static>(
+  env: &Env,
+  mut task: T,
+) -> napi::Result<PromiseRaw<'_, T::JsValue>> {
+  env.spawn_future_with_callback(
+    async move {
+      spawn_blocking(move || {
+        let output = task.compute();
+        (task, output)
+      })
+      .await
+      .map_err(|err| napi::Error::from_reason(err.to_string()))
+    },
+    |env, (mut task, output)| match output {
+      Ok(output) => task.resolve(*env, output),
+      Err(err) => task.reject(*env, err),
+    },
+  )
+}
+
+/// Does the same as `tar::Builder::append_dir_all`, following symlinks, but
+/// appends regular files with `sparse::append_file` and records every
+/// appended entry in `progress`.
+fn append_dir_all(
+  builder: &mut tar::Builder<BuilderOutput>,
+  name: &Path,
+  src: &Path,
+  progress: &mut ProgressReporter,
+) -> napi::Result<()> {
+  let mut stack = vec![(src.to_path_buf(), true)];
+  while let Some((path, is_dir)) = stack.pop() {
+    let dest = name.join(path.strip_prefix(src).unwrap_or(&path));
+    let bytes = if is_dir {
+      for entry in std::fs::read_dir(&path)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        // Symlinks to directories are followed, like `tar::Builder` does by
+        // default.
+        let is_dir = file_type.is_dir() || (file_type.is_symlink() && entry.path().is_dir());
+        stack.push((entry.path(), is_dir));
+      }
+      if dest.as_os_str().is_empty() {
+        continue;
+      }
+      builder.append_dir(&dest, &path)?;
+      0
+    } else {
+      let metadata = std::fs::metadata(&path)?;
+      if metadata.is_file() {
+        sparse::append_file(builder, &dest, &mut File::open(&path)?)?;
+      } else {
+        builder.append_path_with_name(&path, &dest)?;
+      }
+      metadata.len()
+    };
+    progress.entry_done(&dest.to_string_lossy(), bytes, None)?;
+  }
+  Ok(())
+}
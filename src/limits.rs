@@ -0,0 +1,151 @@
+use std::{io, path::Path, sync::OnceLock};
+
+use napi::{Env, JsValue};
+use napi_derive::napi;
+
+/// The compression ratio is only checked past this many decompressed bytes,
+/// so that the headers at the start of an archive, which compress very well,
+/// do not trip it.
+const RATIO_GRACE_BYTES: u64 = 1024 * 1024;
+
+/// The `code` of the errors reporting a limit violation.
+pub(crate) const LIMIT_EXCEEDED: &str = "LimitExceeded";
+
+#[napi(object, object_to_js = false)]
+#[derive(Default)]
+/// Resource limits of an archive, none by default.
+///
+/// Exceeding one of them fails the current operation with an error whose
+/// `code` is `LimitExceeded` and whose message starts with `LimitExceeded:`.
+/// The async iterators of `entriesAsync` and `createReadStream` can only
+/// report it in the message.
+pub struct ArchiveLimits {
+  /// Maximum number of bytes of the decompressed archive, headers included.
+  pub max_total_bytes: Option<i64>,
+  /// Maximum size of the contents of a single entry.
+  pub max_entry_size: Option<i64>,
+  /// Maximum number of entries.
+  pub max_entries: Option<u32>,
+  /// Maximum length of the path of an entry, in bytes.
+  pub max_path_length: Option<u32>,
+  /// Maximum ratio between the decompressed size of the archive and the
+  /// number of input bytes read so far. Checked once more than 1 MiB has been
+  /// decompressed.
+  pub max_compression_ratio: Option<f64>,
+}
+
+/// The limits of an archive, shared by its `ArchiveSource` and the readers of
+/// its entries.
+///
+/// `tar` wraps the errors of its reader into errors which do not always keep
+/// the original message, so the first violation is recorded here, to be
+/// reported instead of the wrapping error.
+#[derive(Default)]
+pub(crate) struct Limits {
+  max_total_bytes: Option<u64>,
+  max_entry_size: Option<u64>,
+  max_entries: Option<u32>,
+  max_path_length: Option<u32>,
+  max_compression_ratio: Option<f64>,
+  exceeded: OnceLock<String>,
+}
+
+impl Limits {
+  pub(crate) fn new(limits: ArchiveLimits) -> Self {
+    Self {
+      max_total_bytes: limits.max_total_bytes.map(|max| max.max(0) as u64),
+      max_entry_size: limits.max_entry_size.map(|max| max.max(0) as u64),
+      max_entries: limits.max_entries,
+      max_path_length: limits.max_path_length,
+      max_compression_ratio: limits.max_compression_ratio,
+      exceeded: OnceLock::new(),
+    }
+  }
+
+  /// Checks the amount of data read so far, `decompressed` bytes out of
+  /// `compressed` input bytes.
+  pub(crate) fn check_read(&self, decompressed: u64, compressed: u64) -> io::Result<()> {
+    if let Some(max) = self.max_total_bytes.filter(|max| decompressed > *max) {
+      return Err(io::Error::other(self.exceed(format!(
+        "the decompressed archive is larger than maxTotalBytes ({max})"
+      ))));
+    }
+    if let Some(max) = self.max_compression_ratio
+      && decompressed > RATIO_GRACE_BYTES
+      && decompressed as f64 / compressed.max(1) as f64 > max
+    {
+      return Err(io::Error::other(self.exceed(format!(
+        "the compression ratio is higher than maxCompressionRatio ({max})"
+      ))));
+    }
+    Ok(())
+  }
+
+  /// Checks an entry of `size` bytes at `path`, `count` being the number of
+  /// entries read so far, including this one.
+  pub(crate) fn check_entry(&self, count: u32, path: &Path, size: u64) -> napi::Result<()> {
+    let reason = if let Some(max) = self.max_entries.filter(|max| count > *max) {
+      format!("the archive has more entries than maxEntries ({max})")
+    } else if let Some(max) = self
+      .max_path_length
+      .filter(|max| path.as_os_str().len() > *max as usize)
+    {
+      format!(
+        "the path of `{}` is longer than maxPathLength ({max})",
+        path.display()
+      )
+    } else if let Some(max) = self.max_entry_size.filter(|max| size > *max) {
+      format!("`{}` is larger than maxEntrySize ({max})", path.display())
+    } else {
+      return Ok(());
+    };
+    Err(napi::Error::from_reason(self.exceed(reason)))
+  }
+
+  /// Fails with a violation of a limit which is not part of `ArchiveLimits`,
+  /// such as the `maxTotalSize` option of `Archive.toMap`.
+  pub(crate) fn exceeded(&self, reason: String) -> napi::Error {
+    napi::Error::from_reason(self.exceed(reason))
+  }
+
+  /// The limit violation `err` originates from, if any, otherwise `err`.
+  pub(crate) fn exceeded_or(&self, err: napi::Error) -> napi::Error {
+    match self.exceeded.get() {
+      Some(reason) => napi::Error::from_reason(reason.clone()),
+      None => err,
+    }
+  }
+
+  pub(crate) fn is_exceeded(&self) -> bool {
+    self.exceeded.get().is_some()
+  }
+
+  /// Whether `err` reports the limit violation.
+  pub(crate) fn is_violation(&self, err: &napi::Error) -> bool {
+    self.exceeded.get() == Some(&err.reason)
+  }
+
+  /// Gives `err` the `LimitExceeded` code if it reports the limit violation,
+  /// which a `napi::Status` cannot express, so that it can be told apart from
+  /// the other failures.
+  pub(crate) fn with_code(&self, env: &Env, err: napi::Error) -> napi::Error {
+    if !self.is_violation(&err) {
+      return err;
+    }
+    let coded = env
+      .create_error(napi::Error::from_reason(err.reason.clone()))
+      .and_then(|mut error| {
+        error.set("code", LIMIT_EXCEEDED)?;
+        Ok(napi::Error::from(error.to_unknown()))
+      });
+    coded.unwrap_or(err)
+  }
+
+  /// Records a violation, returns the message to report.
+  fn exceed(&self, reason: String) -> String {
+    self
+      .exceeded
+      .get_or_init(|| format!("LimitExceeded: {reason}"))
+      .clone()
+  }
+}
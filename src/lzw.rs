@@ -0,0 +1,191 @@
+use std::io::{self, Read};
+
+const MAGIC: [u8; 2] = [0x1F, 0x9D];
+const INIT_BITS: u32 = 9;
+const CLEAR: u32 = 256;
+/// The compressor emits codes in groups of eight, so a group of `n` bit codes
+/// spans exactly `n` bytes.
+const GROUP_SIZE: u32 = 8;
+
+/// Streaming decoder for the LZW format of the Unix `compress` utility (`.Z`).
+///
+/// Codes are stored least significant bit first, start at 9 bits and grow up
+/// to the maximum announced in the header. Whenever the code width changes, or
+/// the table is cleared, the compressor pads the current group of codes, so
+/// the decoder has to skip the remainder of that group too.
+pub(crate) struct LzwDecoder<R> {
+  inner: R,
+  header_read: bool,
+  max_bits: u32,
+  block_mode: bool,
+  n_bits: u32,
+  free_ent: u32,
+  codes_in_group: u32,
+  bit_buf: u32,
+  bit_count: u32,
+  prefix: Vec<u16>,
+  suffix: Vec<u8>,
+  old_code: Option<u32>,
+  fin_char: u8,
+  stack: Vec<u8>,
+  output: Vec<u8>,
+  output_pos: usize,
+  finished: bool,
+}
+
+impl<R: Read> LzwDecoder<R> {
+  pub(crate) fn new(inner: R) -> Self {
+    Self {
+      inner,
+      header_read: false,
+      max_bits: 16,
+      block_mode: true,
+      n_bits: INIT_BITS,
+      free_ent: CLEAR + 1,
+      codes_in_group: 0,
+      bit_buf: 0,
+      bit_count: 0,
+      prefix: Vec::new(),
+      suffix: Vec::new(),
+      old_code: None,
+      fin_char: 0,
+      stack: Vec::new(),
+      output: Vec::new(),
+      output_pos: 0,
+      finished: false,
+    }
+  }
+
+  fn read_header(&mut self) -> io::Result<()> {
+    let mut header = [0u8; 3];
+    self.inner.read_exact(&mut header)?;
+    if header[..2] != MAGIC {
+      return Err(corrupted("invalid magic"));
+    }
+    self.max_bits = u32::from(header[2] & 0x1F);
+    if !(INIT_BITS..=16).contains(&self.max_bits) {
+      return Err(corrupted("unsupported maximum code width"));
+    }
+    self.block_mode = header[2] & 0x80 != 0;
+    self.free_ent = if self.block_mode { CLEAR + 1 } else { CLEAR };
+    let table_size = 1 << self.max_bits;
+    self.prefix = vec![0; table_size];
+    self.suffix = (0..table_size).map(|code| code as u8).collect();
+    self.header_read = true;
+    Ok(())
+  }
+
+  /// Reads the next `n_bits` wide code, `None` at the end of the input.
+  fn read_code(&mut self) -> io::Result<Option<u32>> {
+    while self.bit_count < self.n_bits {
+      let mut byte = [0u8; 1];
+      if self.inner.read(&mut byte)? == 0 {
+        return Ok(None);
+      }
+      self.bit_buf |= u32::from(byte[0]) << self.bit_count;
+      self.bit_count += 8;
+    }
+    let code = self.bit_buf & ((1 << self.n_bits) - 1);
+    self.bit_buf >>= self.n_bits;
+    self.bit_count -= self.n_bits;
+    self.codes_in_group = (self.codes_in_group + 1) % GROUP_SIZE;
+    Ok(Some(code))
+  }
+
+  /// Discards the padding codes up to the end of the current group.
+  fn skip_group(&mut self) -> io::Result<()> {
+    while self.codes_in_group != 0 {
+      if self.read_code()?.is_none() {
+        break;
+      }
+    }
+    self.codes_in_group = 0;
+    Ok(())
+  }
+
+  /// Decodes the next code into `output`, returns `false` at the end of the
+  /// input.
+  fn decode_next(&mut self) -> io::Result<bool> {
+    if self.n_bits < self.max_bits && self.free_ent >= 1 << self.n_bits {
+      self.skip_group()?;
+      self.n_bits += 1;
+    }
+
+    let Some(code) = self.read_code()? else {
+      return Ok(false);
+    };
+
+    let Some(old_code) = self.old_code else {
+      if code >= CLEAR {
+        return Err(corrupted("invalid first code"));
+      }
+      self.fin_char = code as u8;
+      self.old_code = Some(code);
+      self.output.push(self.fin_char);
+      return Ok(true);
+    };
+
+    if code == CLEAR && self.block_mode {
+      self.skip_group()?;
+      self.n_bits = INIT_BITS;
+      self.free_ent = CLEAR;
+      return Ok(true);
+    }
+
+    let in_code = code;
+    let mut code = code;
+    self.stack.clear();
+    if code >= self.free_ent {
+      if code > self.free_ent {
+        return Err(corrupted("invalid code"));
+      }
+      self.stack.push(self.fin_char);
+      code = old_code;
+    }
+    while code >= CLEAR {
+      if self.stack.len() >= self.prefix.len() {
+        return Err(corrupted("invalid code chain"));
+      }
+      self.stack.push(self.suffix[code as usize]);
+      code = u32::from(self.prefix[code as usize]);
+    }
+    self.fin_char = self.suffix[code as usize];
+    self.stack.push(self.fin_char);
+    self.output.extend(self.stack.iter().rev());
+
+    if (self.free_ent as usize) < self.prefix.len() {
+      self.prefix[self.free_ent as usize] = old_code as u16;
+      self.suffix[self.free_ent as usize] = self.fin_char;
+      self.free_ent += 1;
+    }
+    self.old_code = Some(in_code);
+    Ok(true)
+  }
+}
+
+impl<R: Read> Read for LzwDecoder<R> {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    if !self.header_read {
+      self.read_header()?;
+    }
+    while self.output_pos == self.output.len() && !self.finished {
+      self.output.clear();
+      self.output_pos = 0;
+      if !self.decode_next()? {
+        self.finished = true;
+      }
+    }
+    let available = &self.output[self.output_pos..];
+    let len = available.len().min(buf.len());
+    buf[..len].copy_from_slice(&available[..len]);
+    self.output_pos += len;
+    Ok(len)
+  }
+}
+
+fn corrupted(reason: &str) -> io::Error {
+  io::Error::new(
+    io::ErrorKind::InvalidData,
+    format!("corrupted compress (.Z) stream: {reason}"),
+  )
+}
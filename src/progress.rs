@@ -0,0 +1,119 @@
+use std::time::{Duration, Instant};
+
+use napi::{
+  Env,
+  bindgen_prelude::FunctionRef,
+  threadsafe_function::{ThreadsafeFunction, ThreadsafeFunctionCallMode},
+};
+use napi_derive::napi;
+
+const DEFAULT_INTERVAL: Duration = Duration::from_millis(100);
+
+#[napi(object, object_from_js = false)]
+#[derive(Clone, Default)]
+/// Progress of a running `unpack`, `unpackAsync` or `appendDirAll`.
+pub struct Progress {
+  /// Number of entries written so far.
+  pub entries_done: u32,
+  /// Size of the contents of the entries written so far.
+  pub bytes_done: u64,
+  /// Path of the last entry written.
+  pub current_path: Option<String>,
+  /// Number of bytes read from the input of the archive, before
+  /// decompression. Not set when building an archive.
+  pub compressed_bytes_read: Option<u64>,
+}
+
+/// The `onProgress` callback of the options, called from the thread doing the
+/// work.
+pub(crate) type OnProgress<'a> = &'a dyn Fn(Progress) -> napi::Result<()>;
+
+pub(crate) type OnProgressCallback = FunctionRef<Progress, ()>;
+
+pub(crate) type ThreadsafeOnProgress =
+  ThreadsafeFunction<Progress, (), Progress, napi::Status, false, true>;
+
+/// Makes the `onProgress` callback callable from the worker thread of
+/// `unpackAsync`.
+pub(crate) fn threadsafe_on_progress(
+  env: &Env,
+  on_progress: &OnProgressCallback,
+) -> napi::Result<ThreadsafeOnProgress> {
+  on_progress
+    .borrow_back(env)?
+    .build_threadsafe_function()
+    .callee_handled::<false>()
+    .weak::<true>()
+    .build()
+}
+
+/// Queues a progress report without waiting for the JavaScript thread.
+pub(crate) fn report_threadsafe(
+  on_progress: &ThreadsafeOnProgress,
+  progress: Progress,
+) -> napi::Result<()> {
+  match on_progress.call(progress, ThreadsafeFunctionCallMode::NonBlocking) {
+    napi::Status::Ok => Ok(()),
+    status => Err(napi::Error::new(status, "Failed to report progress")),
+  }
+}
+
+/// Accumulates the progress of an operation and reports it at most once per
+/// interval, plus once when the operation completes.
+pub(crate) struct ProgressReporter<'a> {
+  on_progress: Option<OnProgress<'a>>,
+  interval: Duration,
+  last_report: Option<Instant>,
+  reported: bool,
+  progress: Progress,
+}
+
+impl<'a> ProgressReporter<'a> {
+  /// `interval` is in milliseconds.
+  pub(crate) fn new(on_progress: Option<OnProgress<'a>>, interval: Option<u32>) -> Self {
+    Self {
+      on_progress,
+      interval: interval.map_or(DEFAULT_INTERVAL, |ms| Duration::from_millis(ms.into())),
+      last_report: None,
+      reported: true,
+      progress: Progress::default(),
+    }
+  }
+
+  /// Records a written entry of `bytes` bytes.
+  pub(crate) fn entry_done(
+    &mut self,
+    path: &str,
+    bytes: u64,
+    compressed_bytes_read: Option<u64>,
+  ) -> napi::Result<()> {
+    self.progress.entries_done += 1;
+    let Some(on_progress) = self.on_progress else {
+      return Ok(());
+    };
+    self.progress.bytes_done += bytes;
+    self.progress.current_path = Some(path.to_owned());
+    self.progress.compressed_bytes_read = compressed_bytes_read;
+    self.reported = false;
+    if self
+      .last_report
+      .is_none_or(|last_report| last_report.elapsed() >= self.interval)
+    {
+      self.last_report = Some(Instant::now());
+      self.reported = true;
+      on_progress(self.progress.clone())?;
+    }
+    Ok(())
+  }
+
+  /// Reports the final progress, unless it was reported already.
+  pub(crate) fn finish(&mut self) -> napi::Result<()> {
+    match self.on_progress {
+      Some(on_progress) if !self.reported => {
+        self.reported = true;
+        on_progress(self.progress.clone())
+      }
+      _ => Ok(()),
+    }
+  }
+}
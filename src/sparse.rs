@@ -0,0 +1,434 @@
+use std::{
+  fs::File,
+  io::{self, Read, Seek, SeekFrom, Write},
+  path::Path,
+};
+
+use crate::unpack::{UnpackSettings, create_file};
+
+/// A run of data of a sparse file, the rest of the file being holes.
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct Segment {
+  offset: u64,
+  len: u64,
+}
+
+impl Segment {
+  fn end(&self) -> u64 {
+    self.offset + self.len
+  }
+}
+
+/// A sparse entry in one of the PAX formats of GNU tar (0.0, 0.1 and 1.0),
+/// which `tar` reads as a regular file holding the data segments only.
+///
+/// Old GNU sparse entries, of type `S`, are expanded by `tar` itself.
+pub(crate) struct SparseEntry {
+  /// The path of the file, `GNU.sparse.name`.
+  name: Option<String>,
+  real_size: u64,
+  segments: Vec<Segment>,
+  /// Format 1.0 stores the map at the start of the entry data, it is read
+  /// along with the content.
+  map_pending: bool,
+  /// Position in the logical content.
+  pos: u64,
+  /// Index of the first segment which does not end before `pos`.
+  segment: usize,
+}
+
+impl SparseEntry {
+  /// The sparse map of `entry`, `None` if it is not a PAX sparse entry.
+  pub(crate) fn from_entry(entry: &mut tar::Entry<'_, impl Read>) -> io::Result<Option<Self>> {
+    if !entry.header().entry_type().is_file() {
+      return Ok(None);
+    }
+    let Some(extensions) = entry.pax_extensions()? else {
+      return Ok(None);
+    };
+    let mut version = (None, None);
+    let mut name = None;
+    let mut real_size = None;
+    let mut numbers = Vec::new();
+    for record in extensions {
+      let record = record?;
+      let value = record
+        .value()
+        .map_err(|_| invalid("a record is not valid UTF-8"))?;
+      match record.key_bytes() {
+        b"GNU.sparse.major" => version.0 = Some(value.to_owned()),
+        b"GNU.sparse.minor" => version.1 = Some(value.to_owned()),
+        b"GNU.sparse.name" => name = Some(value.to_owned()),
+        b"GNU.sparse.realsize" | b"GNU.sparse.size" => real_size = Some(parse(value)?),
+        // Format 0.1 lists the whole map in one record.
+        b"GNU.sparse.map" => {
+          for number in value.split(',').filter(|number| !number.is_empty()) {
+            numbers.push(parse(number)?);
+          }
+        }
+        // Format 0.0 repeats these records, in order.
+        b"GNU.sparse.offset" | b"GNU.sparse.numbytes" => numbers.push(parse(value)?),
+        _ => {}
+      }
+    }
+    let map_pending = match version {
+      (Some(major), Some(minor)) if major == "1" && minor == "0" => true,
+      (Some(major), minor) => {
+        return Err(invalid(&format!(
+          "version {major}.{} is not supported",
+          minor.unwrap_or_default()
+        )));
+      }
+      (None, _) if real_size.is_some() => false,
+      (None, _) => return Ok(None),
+    };
+    let Some(real_size) = real_size else {
+      return Err(invalid("the real size of the file is missing"));
+    };
+    let mut sparse = Self {
+      name,
+      real_size,
+      segments: Vec::new(),
+      map_pending,
+      pos: 0,
+      segment: 0,
+    };
+    if !map_pending {
+      sparse.set_segments(&numbers)?;
+    }
+    Ok(Some(sparse))
+  }
+
+  /// The path of the file, which the path of the entry only refers to.
+  pub(crate) fn name(&self) -> Option<&str> {
+    self.name.as_deref()
+  }
+
+  /// The size of the file, holes included.
+  pub(crate) fn size(&self) -> u64 {
+    self.real_size
+  }
+
+  /// Reads the logical content of the file out of `data`, the content of the
+  /// entry, filling holes with zeros.
+  pub(crate) fn read(&mut self, data: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    self.read_map(data)?;
+    if self.pos >= self.real_size || buf.is_empty() {
+      return Ok(0);
+    }
+    let pos = self.pos;
+    while self
+      .segments
+      .get(self.segment)
+      .is_some_and(|segment| segment.end() <= pos)
+    {
+      self.segment += 1;
+    }
+    let next = self.segments.get(self.segment);
+    let len = match next {
+      Some(segment) if segment.offset <= pos => {
+        let len = buf.len().min((segment.end() - pos) as usize);
+        let len = data.read(&mut buf[..len])?;
+        if len == 0 {
+          return Err(truncated());
+        }
+        len
+      }
+      _ => {
+        let hole_end = next.map_or(self.real_size, |segment| segment.offset);
+        let len = buf.len().min((hole_end - pos) as usize);
+        buf[..len].fill(0);
+        len
+      }
+    };
+    self.pos += len as u64;
+    Ok(len)
+  }
+
+  /// Writes the file at `dst` out of `entry`, seeking past holes rather than
+  /// writing zeros, then applies the permissions and modification time of
+  /// the entry.
+  pub(crate) fn unpack(
+    &mut self,
+    entry: &mut tar::Entry<'_, impl Read>,
+    dst: &Path,
+    settings: UnpackSettings,
+  ) -> io::Result<()> {
+    self.read_map(entry)?;
+    let mut file = create_file(dst, settings.overwrite)?;
+    for segment in &self.segments {
+      file.seek(SeekFrom::Start(segment.offset))?;
+      if io::copy(&mut (&mut *entry).take(segment.len), &mut file)? < segment.len {
+        return Err(truncated());
+      }
+    }
+    file.set_len(self.real_size)?;
+    self.pos = self.real_size;
+    settings.apply(&file, entry)
+  }
+
+  /// Reads the map of format 1.0: decimal numbers, one per line, the number
+  /// of segments followed by the offset and the length of each, padded to a
+  /// whole 512 bytes block.
+  fn read_map(&mut self, data: &mut impl Read) -> io::Result<()> {
+    if !self.map_pending {
+      return Ok(());
+    }
+    let mut text = Vec::new();
+    let mut lines = 0;
+    // Known once the first line, the number of segments, is read.
+    let mut needed: Option<u64> = None;
+    while needed.is_none_or(|needed| lines < needed) {
+      let mut block = [0; 512];
+      data
+        .read_exact(&mut block)
+        .map_err(|err| match err.kind() {
+          io::ErrorKind::UnexpectedEof => truncated(),
+          _ => err,
+        })?;
+      text.extend_from_slice(&block);
+      lines += block.iter().filter(|b| **b == b'\n').count() as u64;
+      if needed.is_none()
+        && let Some(count) = text.split(|b| *b == b'\n').next().filter(|_| lines > 0)
+      {
+        let count = parse(std::str::from_utf8(count).unwrap_or_default())?;
+        needed = Some(
+          count
+            .checked_mul(2)
+            .and_then(|lines| lines.checked_add(1))
+            .ok_or_else(|| invalid("the number of segments is too large"))?,
+        );
+      }
+    }
+    let needed = needed.unwrap_or(1);
+    let numbers = text
+      .split(|b| *b == b'\n')
+      .skip(1)
+      .take((needed - 1) as usize)
+      .map(|number| parse(std::str::from_utf8(number).unwrap_or_default()))
+      .collect::<io::Result<Vec<_>>>()?;
+    self.set_segments(&numbers)?;
+    self.map_pending = false;
+    Ok(())
+  }
+
+  fn set_segments(&mut self, numbers: &[u64]) -> io::Result<()> {
+    if !numbers.len().is_multiple_of(2) {
+      return Err(invalid("a segment has no length"));
+    }
+    let mut end = 0;
+    for pair in numbers.chunks_exact(2) {
+      let segment = Segment {
+        offset: pair[0],
+        len: pair[1],
+      };
+      if segment.offset < end
+        || segment
+          .offset
+          .checked_add(segment.len)
+          .is_none_or(|end| end > self.real_size)
+      {
+        return Err(invalid(
+          "the segments overlap or exceed the size of the file",
+        ));
+      }
+      end = segment.end();
+      self.segments.push(segment);
+    }
+    Ok(())
+  }
+}
+
+fn parse(number: &str) -> io::Result<u64> {
+  number
+    .trim()
+    .parse()
+    .map_err(|_| invalid(&format!("`{number}` is not a valid number")))
+}
+
+fn invalid(reason: &str) -> io::Error {
+  io::Error::new(
+    io::ErrorKind::InvalidData,
+    format!("invalid sparse entry: {reason}"),
+  )
+}
+
+fn truncated() -> io::Error {
+  io::Error::new(
+    io::ErrorKind::UnexpectedEof,
+    "the sparse entry is shorter than its map",
+  )
+}
+
+/// Appends `file` as `name`, as a PAX 1.0 sparse entry if it has holes.
+///
+/// This replaces the sparse support of `tar::Builder`, which writes old GNU
+/// sparse entries.
+pub(crate) fn append_file(
+  builder: &mut tar::Builder<impl Write>,
+  name: &Path,
+  file: &mut File,
+) -> io::Result<()> {
+  let metadata = file.metadata()?;
+  let segments = data_segments(file, metadata.len())?;
+  let (Some(segments), Some(name_str)) = (segments, name.to_str()) else {
+    file.rewind()?;
+    return builder.append_file(name, file);
+  };
+
+  let mut map = format!("{}\n", segments.len());
+  for segment in &segments {
+    map.push_str(&format!("{}\n{}\n", segment.offset, segment.len));
+  }
+  let mut map = map.into_bytes();
+  map.resize(map.len().next_multiple_of(512), 0);
+
+  let mut records = Vec::new();
+  for (key, value) in [
+    ("GNU.sparse.major", "1"),
+    ("GNU.sparse.minor", "0"),
+    ("GNU.sparse.name", name_str),
+    ("GNU.sparse.realsize", &metadata.len().to_string()),
+  ] {
+    records.extend_from_slice(&pax_record(key, value));
+  }
+  let file_name = name.file_name().unwrap_or(name.as_os_str());
+  let mut pax_header = tar::Header::new_ustar();
+  pax_header.set_entry_type(tar::EntryType::XHeader);
+  // Like GNU tar, cut the name to the 100 bytes of the name field: readers
+  // take the path of the entry from its `GNU.sparse.name` record.
+  let pax_name = [b"PaxHeaders.0/", file_name.as_encoded_bytes()].concat();
+  let field = &mut pax_header.as_old_mut().name;
+  let len = pax_name.len().min(field.len());
+  field[..len].copy_from_slice(&pax_name[..len]);
+  pax_header.set_size(records.len() as u64);
+  pax_header.set_mode(0o644);
+  pax_header.set_mtime(metadata_mtime(&metadata));
+  pax_header.set_cksum();
+  builder.append(&pax_header, records.as_slice())?;
+
+  // Readers without sparse support extract the map and data segments under
+  // this path, like with GNU tar.
+  let path = name
+    .parent()
+    .unwrap_or(Path::new(""))
+    .join("GNUSparseFile.0")
+    .join(file_name);
+  let stored_size = map.len() as u64 + segments.iter().map(|segment| segment.len).sum::<u64>();
+  // GNU tar ignores the PAX sparse records of an entry with a GNU header.
+  let mut header = tar::Header::new_ustar();
+  header.set_metadata(&metadata);
+  header.set_size(stored_size);
+  let data = io::Cursor::new(map).chain(SegmentsReader {
+    file,
+    segments: segments.iter(),
+    remaining: 0,
+  });
+  builder.append_data(&mut header, path, data)
+}
+
+fn pax_record(key: &str, value: &str) -> Vec<u8> {
+  // The length of the record includes its own digits.
+  let len = key.len() + value.len() + 3;
+  let mut total = len + len.to_string().len();
+  if total.to_string().len() != len.to_string().len() {
+    total += 1;
+  }
+  format!("{total} {key}={value}\n").into_bytes()
+}
+
+fn metadata_mtime(metadata: &std::fs::Metadata) -> u64 {
+  metadata
+    .modified()
+    .ok()
+    .and_then(|mtime| mtime.duration_since(std::time::UNIX_EPOCH).ok())
+    .map_or(0, |mtime| mtime.as_secs())
+}
+
+/// Reads the data segments of a file one after the other.
+struct SegmentsReader<'a> {
+  file: &'a mut File,
+  segments: std::slice::Iter<'a, Segment>,
+  remaining: u64,
+}
+
+impl Read for SegmentsReader<'_> {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    while self.remaining == 0 {
+      let Some(segment) = self.segments.next() else {
+        return Ok(0);
+      };
+      self.file.seek(SeekFrom::Start(segment.offset))?;
+      self.remaining = segment.len;
+    }
+    let len = buf.len().min(self.remaining as usize);
+    let len = self.file.read(&mut buf[..len])?;
+    if len == 0 {
+      return Err(io::Error::new(
+        io::ErrorKind::UnexpectedEof,
+        "the file was truncated while being appended",
+      ));
+    }
+    self.remaining -= len as u64;
+    Ok(len)
+  }
+}
+
+/// The data segments of a file with holes, `None` if it has none or holes
+/// can't be detected.
+#[cfg(any(target_os = "android", target_os = "freebsd", target_os = "linux"))]
+fn data_segments(file: &File, size: u64) -> io::Result<Option<Vec<Segment>>> {
+  use std::os::fd::AsRawFd;
+
+  #[cfg(any(target_os = "android", target_os = "linux"))]
+  let lseek = libc::lseek64;
+  #[cfg(target_os = "freebsd")]
+  let lseek = libc::lseek;
+  // `None` once there is no data after `offset`.
+  let seek = |offset: u64, whence| match unsafe { lseek(file.as_raw_fd(), offset as i64, whence) } {
+    -1 => match io::Error::last_os_error() {
+      err if err.raw_os_error() == Some(libc::ENXIO) => Ok(None),
+      err => Err(err),
+    },
+    offset => Ok(Some(offset as u64)),
+  };
+
+  let mut segments = Vec::new();
+  let mut offset = 0;
+  while offset < size {
+    // File systems without hole support fail, or report a single segment.
+    let Ok(start) = seek(offset, libc::SEEK_DATA) else {
+      return Ok(None);
+    };
+    let Some(start) = start.filter(|start| *start < size) else {
+      break;
+    };
+    let end = seek(start, libc::SEEK_HOLE)?.unwrap_or(size).min(size);
+    if end <= start {
+      return Err(io::Error::other(
+        "the file changed while looking for its holes",
+      ));
+    }
+    segments.push(Segment {
+      offset: start,
+      len: end - start,
+    });
+    offset = end;
+  }
+  if size == 0 || (segments.len() == 1 && segments[0].len == size) {
+    return Ok(None);
+  }
+  // Like GNU tar, end the map at the end of the file.
+  if segments.last().is_none_or(|segment| segment.end() < size) {
+    segments.push(Segment {
+      offset: size,
+      len: 0,
+    });
+  }
+  Ok(Some(segments))
+}
+
+#[cfg(not(any(target_os = "android", target_os = "freebsd", target_os = "linux")))]
+fn data_segments(_file: &File, _size: u64) -> io::Result<Option<Vec<Segment>>> {
+  Ok(None)
+}
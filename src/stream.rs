@@ -0,0 +1,127 @@
+use std::{
+  io::{self, Read},
+  thread::{self, ThreadId},
+};
+
+use napi::{
+  Env, JsValue,
+  bindgen_prelude::{FromNapiValue, Function, JsObjectValue, Object, Promise, Unknown, block_on},
+  sys,
+  threadsafe_function::ThreadsafeFunction,
+};
+
+type NextChunk = ThreadsafeFunction<(), Promise<StreamChunk>, (), napi::Status, false, true>;
+
+/// Pulls chunks out of a JavaScript async iterable, such as a Node.js
+/// `Readable`.
+///
+/// A new chunk is only requested once the previous one has been consumed, so
+/// the producer is paused while the archive is busy decompressing or writing.
+/// The pull has to wait for the JavaScript thread, which is why this reader
+/// refuses to be used from that thread.
+pub(crate) struct ChunkReader {
+  next: NextChunk,
+  chunk: Vec<u8>,
+  pos: usize,
+  done: bool,
+  js_thread: ThreadId,
+}
+
+impl ChunkReader {
+  pub(crate) fn new(env: &Env, iterable: Object) -> napi::Result<Self> {
+    let async_iterator = env
+      .get_global()?
+      .get_named_property::<Function>("Symbol")?
+      .get_named_property::<Unknown>("asyncIterator")?;
+    let iterator = iterable
+      .get_property::<Unknown, Option<Function<(), Object>>>(async_iterator)?
+      .ok_or_else(|| {
+        napi::Error::new(
+          napi::Status::InvalidArg,
+          "Input is neither a path, a Buffer nor an async iterable",
+        )
+      })?
+      .apply(iterable, ())?;
+    let next = iterator
+      .get_named_property::<Function<(), Promise<StreamChunk>>>("next")?
+      .bind(iterator)?
+      .build_threadsafe_function()
+      .callee_handled::<false>()
+      .weak::<true>()
+      .build()?;
+    Ok(Self {
+      next,
+      chunk: Vec::new(),
+      pos: 0,
+      done: false,
+      js_thread: thread::current().id(),
+    })
+  }
+
+  /// Buffers at least `len` bytes, or everything until the end of the stream,
+  /// and returns them without consuming.
+  pub(crate) fn peek(&mut self, len: usize) -> io::Result<&[u8]> {
+    while self.chunk.len() - self.pos < len && !self.done {
+      let next = self.pull()?;
+      self.chunk.drain(..self.pos);
+      self.pos = 0;
+      match next {
+        Some(chunk) => self.chunk.extend_from_slice(&chunk),
+        None => self.done = true,
+      }
+    }
+    Ok(&self.chunk[self.pos..])
+  }
+
+  fn pull(&self) -> io::Result<Option<Vec<u8>>> {
+    if thread::current().id() == self.js_thread {
+      return Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Archives created from a stream can only be read off the JavaScript thread, with `entriesAsync()` or `unpackAsync()`",
+      ));
+    }
+    block_on(async { self.next.call_async_catch(()).await?.await })
+      .map(|chunk| chunk.0)
+      .map_err(|err| io::Error::other(format!("Failed to read the input stream: {err}")))
+  }
+}
+
+impl Read for ChunkReader {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    while self.pos == self.chunk.len() && !self.done {
+      match self.pull()? {
+        Some(chunk) => {
+          self.chunk = chunk;
+          self.pos = 0;
+        }
+        None => self.done = true,
+      }
+    }
+    let available = &self.chunk[self.pos..];
+    let len = available.len().min(buf.len());
+    buf[..len].copy_from_slice(&available[..len]);
+    self.pos += len;
+    Ok(len)
+  }
+}
+
+/// The `IteratorResult` of an async iterable yielding `Uint8Array`s, copied
+/// on the JavaScript thread. `None` once the iterator is done.
+struct StreamChunk(Option<Vec<u8>>);
+
+impl FromNapiValue for StreamChunk {
+  unsafe fn from_napi_value(env: sys::napi_env, napi_val: sys::napi_value) -> napi::Result<Self> {
+    let result = unsafe { Object::from_napi_value(env, napi_val)? };
+    if result.get::<bool>("done")?.unwrap_or(false) {
+      return Ok(Self(None));
+    }
+    let value = result.get_named_property::<Unknown>("value")?;
+    let chunk = unsafe { <&[u8]>::from_napi_value(env, value.raw()) }.map_err(|_| {
+      napi::Error::new(
+        napi::Status::InvalidArg,
+        "Input stream must yield Buffer or Uint8Array chunks",
+      )
+    })?;
+    Ok(Self(Some(chunk.to_vec())))
+  }
+}
@@ -0,0 +1,876 @@
+use std::{
+  fs::{self, File, FileTimes, OpenOptions},
+  io,
+  path::{Component, Path, PathBuf},
+  sync::{
+    Arc,
+    atomic::{AtomicBool, AtomicU64, Ordering},
+  },
+  time::{Duration, SystemTime},
+};
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use napi::{
+  Env, Task,
+  bindgen_prelude::{
+    FnArgs, FromNapiValue, Function, FunctionRef, JsObjectValue, Object, TypeName,
+    ValidateNapiValue, ValueType, block_on,
+  },
+  threadsafe_function::ThreadsafeFunction,
+};
+use napi_derive::napi;
+
+use crate::ArchiveSource;
+use crate::entry::normalize_archive_path;
+use crate::limits::Limits;
+use crate::progress::{
+  OnProgress, OnProgressCallback, ProgressReporter, ThreadsafeOnProgress, report_threadsafe,
+};
+use crate::sparse::SparseEntry;
+
+#[napi(object, object_to_js = false)]
+#[derive(Default)]
+pub struct UnpackOptions {
+  /// Only unpack the entries matching one of these patterns.
+  ///
+  /// Patterns follow the `.gitignore` syntax and are matched against the
+  /// entry path stored in the archive, with any leading `/` or `./` removed,
+  /// before `stripComponents` and `rename` are applied. A pattern matching a
+  /// directory selects everything below it, and `!pattern` deselects entries
+  /// again. Parent directories of the selected entries are created as needed.
+  pub include: Option<Vec<String>>,
+  /// Skip the entries matching one of these patterns, even if they are
+  /// selected by `include`. Uses the same syntax as `include`.
+  pub exclude: Option<Vec<String>>,
+  /// Remove this many leading components from the path of every entry, like
+  /// `tar --strip-components`. Entries with fewer components are skipped.
+  pub strip_components: Option<u32>,
+  /// Rewrite the path of every entry, after `stripComponents` is applied.
+  /// Return `null` to skip the entry. The target of hard links is rewritten
+  /// the same way.
+  ///
+  /// Rewritten paths go through the same checks as the original ones: paths
+  /// containing `..` are skipped, and nothing is written outside of the
+  /// destination.
+  #[napi(ts_type = "(path: string) => string | null | undefined")]
+  pub rename: Option<FunctionRef<String, Option<String>>>,
+  /// How symlinks and hard links are handled, `allow` by default. See
+  /// `LinkPolicy`.
+  #[napi(ts_type = "'allow' | 'contain' | 'reject'")]
+  pub links: Option<LinkPolicy>,
+  /// Called with the progress of the extraction, at most once per
+  /// `progressInterval` and once more when it completes.
+  ///
+  /// `unpackAsync` queues the calls without waiting for them, so the progress
+  /// is not held back by the JavaScript thread.
+  #[napi(ts_type = "(progress: Progress) => void")]
+  pub on_progress: Option<OnProgressCallback>,
+  /// Minimum number of milliseconds between two `onProgress` calls, 100 by
+  /// default.
+  pub progress_interval: Option<u32>,
+  /// Cancel the extraction. The signal is checked before each entry is
+  /// written, so entries which are already on disk are kept.
+  ///
+  /// Only honoured by `unpackAsync`, which rejects right away when the
+  /// signal is already aborted.
+  #[napi(ts_type = "AbortSignal")]
+  pub signal: Option<UnpackSignal>,
+}
+
+/// The `signal` of `UnpackOptions`.
+///
+/// It listens to the `abort` event instead of going through napi's
+/// `AbortSignal`, which replaces the `onabort` handler of the signal and
+/// misses signals aborted before the call.
+pub struct UnpackSignal(Arc<AtomicBool>);
+
+impl UnpackSignal {
+  pub(crate) fn is_aborted(&self) -> bool {
+    self.0.load(Ordering::Relaxed)
+  }
+}
+
+impl TypeName for UnpackSignal {
+  fn type_name() -> &'static str {
+    "AbortSignal"
+  }
+
+  fn value_type() -> ValueType {
+    ValueType::Object
+  }
+}
+
+impl ValidateNapiValue for UnpackSignal {}
+
+impl FromNapiValue for UnpackSignal {
+  unsafe fn from_napi_value(
+    env: napi::sys::napi_env,
+    value: napi::sys::napi_value,
+  ) -> napi::Result<Self> {
+    let signal = unsafe { Object::from_napi_value(env, value)? };
+    let aborted = Arc::new(AtomicBool::new(
+      signal.get_named_property::<bool>("aborted")?,
+    ));
+    let env = Env::from_raw(env);
+    let listener = {
+      let aborted = aborted.clone();
+      env.create_function_from_closure::<(), (), _>("onabort", move |_| {
+        aborted.store(true, Ordering::Relaxed);
+        Ok(())
+      })?
+    };
+    signal
+      .get_named_property::<Function<FnArgs<(&str, Function<(), ()>)>, ()>>("addEventListener")?
+      .apply(signal, FnArgs::from(("abort", listener)))?;
+    Ok(Self(aborted))
+  }
+}
+
+#[napi(string_enum = "lowercase")]
+#[derive(Clone, Copy, PartialEq, Eq)]
+/// How `unpack` handles link entries. `contain` and `reject` fail on the
+/// first offending entry, with an error naming it.
+pub enum LinkPolicy {
+  /// Unpack links like `tar` does: hard links must point inside the
+  /// destination, symlinks may point anywhere.
+  Allow,
+  /// Reject symlinks with an absolute target, and symlinks or hard links
+  /// whose target resolves outside of the destination. Entries are never
+  /// written through a symlink which already exists in the destination.
+  Contain,
+  /// Reject every link entry, and never write through a symlink which already
+  /// exists in the destination.
+  Reject,
+}
+
+#[napi(object, object_from_js = false)]
+#[derive(Default)]
+/// Outcome of `unpack` and `unpackAsync`.
+pub struct UnpackReport {
+  /// Paths of the written entries, relative to the destination and after
+  /// `stripComponents` and `rename` are applied.
+  pub written: Vec<String>,
+  /// Entries which were rejected, with their path in the archive.
+  ///
+  /// Entries left out by `include`, `exclude`, `stripComponents` or `rename`
+  /// are not rejections, they are not listed.
+  pub skipped: Vec<SkippedEntry>,
+  pub entries_written: u32,
+  /// Size of the contents of the written entries.
+  pub bytes_written: u64,
+  pub entries_skipped: u32,
+}
+
+impl UnpackReport {
+  fn skip(&mut self, path: &Path, reason: SkipReason) {
+    self.entries_skipped += 1;
+    self.skipped.push(SkippedEntry {
+      path: path.to_string_lossy().into_owned(),
+      reason,
+    });
+  }
+}
+
+#[napi(object, object_from_js = false)]
+pub struct SkippedEntry {
+  pub path: String,
+  #[napi(ts_type = "'pathTraversal' | 'unsupportedType' | 'existingFileKept'")]
+  pub reason: SkipReason,
+}
+
+#[napi(string_enum = "camelCase")]
+/// Why an entry was not unpacked.
+pub enum SkipReason {
+  /// The path has a `..` component, or a hard link points outside of the
+  /// destination.
+  PathTraversal,
+  /// Character and block devices and named pipes are not unpacked.
+  UnsupportedType,
+  /// The destination already exists and `setOverwrite(false)` was called.
+  ExistingFileKept,
+}
+
+/// The `rename` hook of `UnpackOptions`, called from the thread unpacking the
+/// archive.
+pub(crate) type Rename<'a> = &'a dyn Fn(String) -> napi::Result<Option<String>>;
+
+pub(crate) type ThreadsafeRename =
+  ThreadsafeFunction<String, Option<String>, String, napi::Status, false, true>;
+
+/// Makes the `rename` hook callable from the worker thread of `unpackAsync`.
+pub(crate) fn threadsafe_rename(
+  env: &Env,
+  rename: &FunctionRef<String, Option<String>>,
+) -> napi::Result<ThreadsafeRename> {
+  rename
+    .borrow_back(env)?
+    .build_threadsafe_function()
+    .callee_handled::<false>()
+    .weak::<true>()
+    .build()
+}
+
+/// The JavaScript callbacks of `UnpackOptions`, callable from the thread
+/// unpacking the archive.
+#[derive(Default)]
+pub(crate) struct UnpackHooks<'a> {
+  pub(crate) rename: Option<Rename<'a>>,
+  pub(crate) on_progress: Option<OnProgress<'a>>,
+}
+
+/// The settings of an archive which apply to unpacked files, mirrored from
+/// `tar::Archive`, which can't read them back.
+///
+/// They are only needed for the files `tar` does not write itself, sparse
+/// entries in the PAX formats.
+#[derive(Clone, Copy)]
+pub(crate) struct UnpackSettings {
+  pub(crate) overwrite: bool,
+  pub(crate) mask: u32,
+  pub(crate) preserve_permissions: bool,
+  pub(crate) preserve_ownerships: bool,
+  pub(crate) preserve_mtime: bool,
+  pub(crate) unpack_xattrs: bool,
+}
+
+impl Default for UnpackSettings {
+  fn default() -> Self {
+    Self {
+      overwrite: true,
+      mask: 0,
+      preserve_permissions: false,
+      preserve_ownerships: false,
+      preserve_mtime: true,
+      unpack_xattrs: false,
+    }
+  }
+}
+
+impl UnpackSettings {
+  /// Applies the modification time, ownership, permissions and extended
+  /// attributes of `entry` to an unpacked file, like `tar` does.
+  pub(crate) fn apply(
+    &self,
+    file: &File,
+    entry: &mut tar::Entry<'_, impl io::Read>,
+  ) -> io::Result<()> {
+    let header = entry.header();
+    if self.preserve_mtime
+      && let Ok(mtime) = header.mtime()
+    {
+      // `tar` avoids 0 mtimes, which some tools do not handle well.
+      let mtime = SystemTime::UNIX_EPOCH + Duration::from_secs(mtime.max(1));
+      file.set_times(FileTimes::new().set_accessed(mtime).set_modified(mtime))?;
+    }
+    // The owner is changed first, as it clears the setuid and setgid bits.
+    #[cfg(unix)]
+    if self.preserve_ownerships {
+      let id = |id: io::Result<u64>, kind: &str| {
+        let id = id?;
+        u32::try_from(id).map_err(|_| {
+          io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{kind} {id} is too large"),
+          )
+        })
+      };
+      std::os::unix::fs::fchown(
+        file,
+        Some(id(header.uid(), "UID")?),
+        Some(id(header.gid(), "GID")?),
+      )?;
+    }
+    #[cfg(unix)]
+    if let Ok(mode) = header.mode() {
+      use std::os::unix::fs::PermissionsExt;
+
+      let mode = if self.preserve_permissions {
+        mode
+      } else {
+        mode & 0o777
+      };
+      file.set_permissions(fs::Permissions::from_mode(mode & !self.mask))?;
+    }
+    #[cfg(unix)]
+    if self.unpack_xattrs
+      && let Some(extensions) = entry.pax_extensions()?
+    {
+      use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+      use xattr::FileExt;
+
+      for extension in extensions {
+        let extension = extension?;
+        if let Some(name) = extension.key_bytes().strip_prefix(b"SCHILY.xattr.") {
+          file.set_xattr(OsStr::from_bytes(name), extension.value_bytes())?;
+        }
+      }
+    }
+    Ok(())
+  }
+}
+
+/// Unpacks the entries of an archive according to `UnpackOptions`.
+pub(crate) struct Unpacker {
+  selection: Selection,
+  strip_components: usize,
+  progress_interval: Option<u32>,
+  settings: UnpackSettings,
+  links: LinkPolicy,
+  /// The input bytes read by the archive, see `ArchiveSource`.
+  bytes_read: Arc<AtomicU64>,
+  /// The limits of the archive, see `ArchiveOptions`.
+  limits: Arc<Limits>,
+  aborted: Arc<AtomicBool>,
+}
+
+impl Unpacker {
+  pub(crate) fn new(
+    options: &UnpackOptions,
+    settings: UnpackSettings,
+    bytes_read: Arc<AtomicU64>,
+    limits: Arc<Limits>,
+  ) -> napi::Result<Self> {
+    let aborted = match &options.signal {
+      Some(signal) => signal.0.clone(),
+      None => Arc::new(AtomicBool::new(false)),
+    };
+    Ok(Self {
+      selection: Selection::new(options.include.as_deref(), options.exclude.as_deref())?,
+      strip_components: options.strip_components.unwrap_or(0) as usize,
+      progress_interval: options.progress_interval,
+      settings,
+      links: options.links.unwrap_or(LinkPolicy::Allow),
+      bytes_read,
+      limits,
+      aborted,
+    })
+  }
+
+  /// Unpacks every selected entry of `archive` into `dst`, and reports which
+  /// ones were written or rejected.
+  ///
+  /// This follows `tar::Archive::unpack`: directories are applied last,
+  /// deepest first, so that their permissions do not interfere with the
+  /// extraction of their children. If the extraction is aborted while
+  /// iterating, the remaining entries are left untouched and a `Cancelled`
+  /// error is returned. The same goes when a limit of the archive is
+  /// exceeded, with a `LimitExceeded` error.
+  pub(crate) fn unpack(
+    &self,
+    archive: &mut tar::Archive<ArchiveSource>,
+    dst: &Path,
+    hooks: UnpackHooks,
+  ) -> napi::Result<UnpackReport> {
+    self
+      .unpack_entries(archive, dst, hooks)
+      .map_err(|err| self.limits.exceeded_or(err))
+  }
+
+  fn unpack_entries(
+    &self,
+    archive: &mut tar::Archive<ArchiveSource>,
+    dst: &Path,
+    hooks: UnpackHooks,
+  ) -> napi::Result<UnpackReport> {
+    let dst = &prepare_dst(dst)?;
+    let rename = hooks.rename;
+    let rewrites = self.strip_components > 0 || rename.is_some();
+    let mut progress = ProgressReporter::new(hooks.on_progress, self.progress_interval);
+
+    let mut report = UnpackReport::default();
+    let mut directories = Vec::new();
+    for (count, entry) in (1..).zip(archive.entries()?) {
+      if self.is_aborted() {
+        return Err(aborted_error(report.entries_written));
+      }
+      let mut file = entry?;
+      let mut sparse = SparseEntry::from_entry(&mut file)?;
+      let path = match sparse.as_ref().and_then(SparseEntry::name) {
+        Some(name) => PathBuf::from(name),
+        None => file.path()?.into_owned(),
+      };
+      let size = sparse.as_ref().map_or(file.size(), SparseEntry::size);
+      self.limits.check_entry(count, &path, size)?;
+      let entry_type = file.header().entry_type();
+      if is_metadata(entry_type) {
+        continue;
+      }
+      if !self.selection.is_selected(&path, entry_type.is_dir()) {
+        continue;
+      }
+      let target = if rewrites {
+        let Some(target) = self.rewrite(&path, rename)? else {
+          continue;
+        };
+        target
+      } else {
+        path.clone()
+      };
+      if let Some(reason) = self.rejection(&file, dst, &target) {
+        report.skip(&path, reason);
+        continue;
+      }
+      let hard_link = match file.link_name()? {
+        Some(link) if entry_type.is_hard_link() && rewrites => {
+          match self.rewrite(&link, rename)? {
+            Some(link) => Some(link),
+            None => continue,
+          }
+        }
+        Some(link) if entry_type.is_hard_link() => Some(link.into_owned()),
+        _ => None,
+      };
+      self.enforce_link_policy(&file, dst, &path, &target, hard_link.as_deref())?;
+      if entry_type.is_dir() {
+        directories.push((file, path, target));
+        continue;
+      }
+      let unpacked = match &mut sparse {
+        Some(sparse) => unpack_at(
+          &mut file,
+          dst,
+          &target,
+          Content::Sparse(sparse, self.settings),
+        )?,
+        None if rewrites => {
+          let content = hard_link
+            .as_deref()
+            .map_or(Content::Entry, Content::HardLink);
+          unpack_at(&mut file, dst, &target, content)?
+        }
+        None => file.unpack_in(dst)?,
+      };
+      if unpacked {
+        self.written(&mut report, &mut progress, size, &target)?;
+      } else {
+        report.skip(&path, SkipReason::PathTraversal);
+      }
+    }
+
+    directories.sort_by(|(_, _, a), (_, _, b)| b.cmp(a));
+    for (mut dir, path, target) in directories {
+      if self.is_aborted() {
+        return Err(aborted_error(report.entries_written));
+      }
+      let unpacked = if rewrites {
+        unpack_at(&mut dir, dst, &target, Content::Entry)?
+      } else {
+        dir.unpack_in(dst)?
+      };
+      if unpacked {
+        self.written(&mut report, &mut progress, dir.size(), &target)?;
+      } else {
+        report.skip(&path, SkipReason::PathTraversal);
+      }
+    }
+
+    progress.finish()?;
+    Ok(report)
+  }
+
+  /// Why `entry` must not be unpacked at `target`, if it must not.
+  fn rejection(
+    &self,
+    entry: &tar::Entry<ArchiveSource>,
+    dst: &Path,
+    target: &Path,
+  ) -> Option<SkipReason> {
+    let entry_type = entry.header().entry_type();
+    if entry_type.is_character_special() || entry_type.is_block_special() || entry_type.is_fifo() {
+      return Some(SkipReason::UnsupportedType);
+    }
+    let Some(file_dst) = join_inside(dst, target) else {
+      return Some(SkipReason::PathTraversal);
+    };
+    // Skip entries whose destination exists, instead of failing.
+    if !self.settings.overwrite && !entry_type.is_dir() && file_dst.symlink_metadata().is_ok() {
+      return Some(SkipReason::ExistingFileKept);
+    }
+    None
+  }
+
+  /// Enforces the `links` policy on an entry about to be unpacked at
+  /// `target`, `hard_link` being the source of a hard link entry.
+  fn enforce_link_policy(
+    &self,
+    entry: &tar::Entry<ArchiveSource>,
+    dst: &Path,
+    path: &Path,
+    target: &Path,
+    hard_link: Option<&Path>,
+  ) -> napi::Result<()> {
+    let entry_type = entry.header().entry_type();
+    match self.links {
+      LinkPolicy::Allow => return Ok(()),
+      LinkPolicy::Reject if entry_type.is_symlink() || entry_type.is_hard_link() => {
+        return Err(link_policy_error(path, "links are rejected"));
+      }
+      LinkPolicy::Contain | LinkPolicy::Reject => {}
+    }
+
+    let target = join_inside(Path::new(""), target).unwrap_or_default();
+    let mut ancestor = dst.to_path_buf();
+    for dir in target.parent().into_iter().flat_map(Path::components) {
+      ancestor.push(dir);
+      if ancestor
+        .symlink_metadata()
+        .is_ok_and(|metadata| metadata.file_type().is_symlink())
+      {
+        let symlink = ancestor.strip_prefix(dst).unwrap_or(&ancestor).display();
+        return Err(link_policy_error(
+          path,
+          &format!("it would be written through the symlink `{symlink}`"),
+        ));
+      }
+    }
+
+    if entry_type.is_symlink() {
+      let link = entry.link_name()?.unwrap_or_default();
+      if link.has_root() {
+        return Err(link_policy_error(
+          path,
+          &format!("its target `{}` is absolute", link.display()),
+        ));
+      }
+      let resolved = target.parent().unwrap_or(Path::new("")).join(&link);
+      if leaves_root(&resolved) {
+        return Err(link_policy_error(
+          path,
+          &format!(
+            "its target `{}` is outside of the destination",
+            link.display()
+          ),
+        ));
+      }
+    }
+    if let Some(link) = hard_link.filter(|link| leaves_root(link)) {
+      return Err(link_policy_error(
+        path,
+        &format!(
+          "its target `{}` is outside of the destination",
+          link.display()
+        ),
+      ));
+    }
+    Ok(())
+  }
+
+  fn written(
+    &self,
+    report: &mut UnpackReport,
+    progress: &mut ProgressReporter,
+    size: u64,
+    target: &Path,
+  ) -> napi::Result<()> {
+    let target = target.to_string_lossy();
+    report.entries_written += 1;
+    report.bytes_written += size;
+    report.written.push(target.clone().into_owned());
+    progress.entry_done(&target, size, Some(self.bytes_read.load(Ordering::Relaxed)))
+  }
+
+  /// Applies `stripComponents` and the `rename` hook to an entry path, `None`
+  /// if the entry is skipped.
+  fn rewrite(&self, path: &Path, rename: Option<Rename>) -> napi::Result<Option<PathBuf>> {
+    let path = path
+      .components()
+      .filter(|component| {
+        !matches!(
+          component,
+          Component::Prefix(_) | Component::RootDir | Component::CurDir
+        )
+      })
+      .skip(self.strip_components)
+      .collect::<PathBuf>();
+    if path.as_os_str().is_empty() {
+      return Ok(None);
+    }
+    match rename {
+      Some(rename) => Ok(rename(path.to_string_lossy().into_owned())?.map(PathBuf::from)),
+      None => Ok(Some(path)),
+    }
+  }
+
+  fn is_aborted(&self) -> bool {
+    self.aborted.load(Ordering::Relaxed)
+  }
+}
+
+/// The entries selected by the `include` and `exclude` patterns of
+/// `UnpackOptions`.
+pub(crate) struct Selection {
+  include: Option<Gitignore>,
+  exclude: Option<Gitignore>,
+}
+
+impl Selection {
+  pub(crate) fn new(include: Option<&[String]>, exclude: Option<&[String]>) -> napi::Result<Self> {
+    Ok(Self {
+      include: compile_patterns("include", include)?,
+      exclude: compile_patterns("exclude", exclude)?,
+    })
+  }
+
+  pub(crate) fn is_selected(&self, path: &Path, is_dir: bool) -> bool {
+    if self.include.is_none() && self.exclude.is_none() {
+      return true;
+    }
+    let path = path
+      .components()
+      .filter(|component| matches!(component, Component::Normal(_) | Component::ParentDir))
+      .collect::<PathBuf>();
+    let matches = |patterns: &Gitignore| {
+      patterns
+        .matched_path_or_any_parents(&path, is_dir)
+        .is_ignore()
+    };
+    self.include.as_ref().is_none_or(matches) && !self.exclude.as_ref().is_some_and(matches)
+  }
+}
+
+/// Compiles gitignore-style `patterns`, `None` if there are none.
+fn compile_patterns(option: &str, patterns: Option<&[String]>) -> napi::Result<Option<Gitignore>> {
+  let Some(patterns) = patterns.filter(|patterns| !patterns.is_empty()) else {
+    return Ok(None);
+  };
+  // Rooted at `.` so that entry paths are matched as they are, without
+  // stripping any prefix.
+  let mut builder = GitignoreBuilder::new(".");
+  for pattern in patterns {
+    builder.add_line(None, pattern).map_err(|err| {
+      napi::Error::new(
+        napi::Status::InvalidArg,
+        format!("Invalid `{option}` pattern `{pattern}`: {err}"),
+      )
+    })?;
+  }
+  builder.build().map(Some).map_err(|err| {
+    napi::Error::new(
+      napi::Status::InvalidArg,
+      format!("Invalid `{option}` patterns: {err}"),
+    )
+  })
+}
+
+/// What `unpack_at` writes.
+pub(crate) enum Content<'a> {
+  /// The entry, written by `tar`.
+  Entry,
+  /// A hard link to this path of the archive.
+  HardLink(&'a Path),
+  /// The file of a PAX sparse entry, with its holes.
+  Sparse(&'a mut SparseEntry, UnpackSettings),
+}
+
+/// Unpacks `entry` at `path`, relative to `dst`, with the same safety checks
+/// as `tar::Entry::unpack_in`: paths containing `..` are skipped, and neither
+/// the entry, its parent directories nor the source of a hard link may resolve
+/// outside of `dst`.
+///
+/// `dst` has to be canonical, see `prepare_dst`.
+pub(crate) fn unpack_at(
+  entry: &mut tar::Entry<ArchiveSource>,
+  dst: &Path,
+  path: &Path,
+  content: Content,
+) -> io::Result<bool> {
+  let Some(file_dst) = join_inside(dst, path) else {
+    return Ok(false);
+  };
+  // Like `tar`, an empty path such as `./` designates `dst` itself.
+  if file_dst == dst {
+    return Ok(true);
+  }
+  let mut parent = dst.to_path_buf();
+  if let Some(dirs) = file_dst.parent().and_then(|dir| dir.strip_prefix(dst).ok()) {
+    for dir in dirs.components() {
+      parent.push(dir);
+      if parent.symlink_metadata().is_err() {
+        fs::create_dir(&parent)?;
+      }
+      ensure_inside(dst, &parent)?;
+    }
+  }
+
+  match content {
+    Content::HardLink(link) => {
+      let Some(link_src) = join_inside(dst, link) else {
+        return Ok(false);
+      };
+      ensure_inside(dst, &link_src)?;
+      fs::hard_link(&link_src, &file_dst).map_err(|err| {
+        io::Error::new(
+          err.kind(),
+          format!(
+            "{err} when hard linking {} to {}",
+            link_src.display(),
+            file_dst.display()
+          ),
+        )
+      })?;
+    }
+    Content::Entry => {
+      entry
+        .unpack(&file_dst)
+        .map_err(|err| unpack_error(&file_dst, err))?;
+    }
+    Content::Sparse(sparse, settings) => {
+      sparse
+        .unpack(entry, &file_dst, settings)
+        .map_err(|err| unpack_error(&file_dst, err))?;
+    }
+  }
+  Ok(true)
+}
+
+fn unpack_error(dst: &Path, err: io::Error) -> io::Error {
+  io::Error::new(
+    err.kind(),
+    format!("failed to unpack `{}`: {err}", dst.display()),
+  )
+}
+
+/// Creates a file to unpack an entry to, replacing an existing one if
+/// `overwrite` is set, like `tar` does.
+pub(crate) fn create_file(dst: &Path, overwrite: bool) -> io::Result<File> {
+  let create = || OpenOptions::new().write(true).create_new(true).open(dst);
+  match create() {
+    Err(err) if err.kind() == io::ErrorKind::AlreadyExists && overwrite => {
+      match fs::remove_file(dst) {
+        Ok(()) => create(),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => create(),
+        Err(err) => Err(err),
+      }
+    }
+    result => result,
+  }
+}
+
+/// Joins the normal components of `path` onto `dst`, `None` if `path` has a
+/// `..` component.
+fn join_inside(dst: &Path, path: &Path) -> Option<PathBuf> {
+  let mut joined = dst.to_path_buf();
+  for component in path.components() {
+    match component {
+      Component::Prefix(_) | Component::RootDir | Component::CurDir => {}
+      Component::ParentDir => return None,
+      Component::Normal(part) => joined.push(part),
+    }
+  }
+  Some(joined)
+}
+
+/// Fails if `path` resolves outside of `dst`, through a symlink for example.
+fn ensure_inside(dst: &Path, path: &Path) -> io::Result<()> {
+  if path.canonicalize()?.starts_with(dst) {
+    Ok(())
+  } else {
+    Err(io::Error::new(
+      io::ErrorKind::InvalidInput,
+      format!(
+        "trying to unpack outside of destination path: {}",
+        dst.display()
+      ),
+    ))
+  }
+}
+
+/// Creates the `dst` directory entries are unpacked in, if it is missing.
+pub(crate) fn prepare_dst(dst: &Path) -> io::Result<PathBuf> {
+  if dst.symlink_metadata().is_err() {
+    fs::create_dir_all(dst)?;
+  }
+  // Canonicalizing prepends `\\?\` on Windows, which lifts the 260 characters
+  // limit of unpacked paths.
+  Ok(dst.canonicalize().unwrap_or(dst.to_path_buf()))
+}
+
+/// Whether `path` is absolute, or climbs above its root once `.` and `..`
+/// are resolved.
+fn leaves_root(path: &Path) -> bool {
+  let resolved = normalize_archive_path(path);
+  resolved == ".." || resolved.starts_with("../") || resolved.starts_with('/')
+}
+
+fn link_policy_error(path: &Path, reason: &str) -> napi::Error {
+  napi::Error::new(
+    napi::Status::InvalidArg,
+    format!("Refusing to unpack `{}`: {reason}", path.display()),
+  )
+}
+
+/// Whether `entry_type` holds metadata of the next entry rather than a file,
+/// `tar` never writes those.
+fn is_metadata(entry_type: tar::EntryType) -> bool {
+  entry_type.is_pax_global_extensions()
+    || entry_type.is_pax_local_extensions()
+    || entry_type.is_gnu_longname()
+    || entry_type.is_gnu_longlink()
+}
+
+pub(crate) fn aborted_error(written: u32) -> napi::Error {
+  napi::Error::new(
+    napi::Status::Cancelled,
+    format!("Unpack aborted after {written} entries were written"),
+  )
+}
+
+pub struct UnpackTask {
+  archive: tar::Archive<ArchiveSource>,
+  dst: PathBuf,
+  unpacker: Unpacker,
+  rename: Option<ThreadsafeRename>,
+  on_progress: Option<ThreadsafeOnProgress>,
+}
+
+impl UnpackTask {
+  pub(crate) fn new(
+    archive: tar::Archive<ArchiveSource>,
+    dst: PathBuf,
+    unpacker: Unpacker,
+    rename: Option<ThreadsafeRename>,
+    on_progress: Option<ThreadsafeOnProgress>,
+  ) -> Self {
+    Self {
+      archive,
+      dst,
+      unpacker,
+      rename,
+      on_progress,
+    }
+  }
+}
+
+impl Task for UnpackTask {
+  type Output = UnpackReport;
+  type JsValue = UnpackReport;
+
+  fn compute(&mut self) -> napi::Result<Self::Output> {
+    let rename = self
+      .rename
+      .as_ref()
+      .map(|rename| move |path| block_on(rename.call_async_catch(path)));
+    let on_progress = self
+      .on_progress
+      .as_ref()
+      .map(|on_progress| move |progress| report_threadsafe(on_progress, progress));
+    self.unpacker.unpack(
+      &mut self.archive,
+      &self.dst,
+      UnpackHooks {
+        rename: rename.as_ref().map(|rename| rename as Rename),
+        on_progress: on_progress
+          .as_ref()
+          .map(|on_progress| on_progress as OnProgress),
+      },
+    )
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+    Ok(output)
+  }
+
+  fn reject(&mut self, env: Env, err: napi::Error) -> napi::Result<Self::JsValue> {
+    Err(self.unpacker.limits.with_code(&env, err))
+  }
+}
@@ -0,0 +1,344 @@
+use std::io::{self, Read};
+
+use napi::{Env, Task};
+use napi_derive::napi;
+
+use crate::{ArchiveSource, has_tar_checksum};
+
+const BLOCK_SIZE: usize = 512;
+
+#[napi(object, object_from_js = false)]
+#[derive(Default)]
+/// Result of `Archive.verify`.
+pub struct VerifyReport {
+  /// Whether no problem was found.
+  pub ok: bool,
+  /// Number of entries read, PAX and GNU extension headers excluded.
+  pub entries: u32,
+  /// Size of the decompressed archive, end-of-archive marker and padding
+  /// included.
+  pub bytes_read: u64,
+  pub problems: Vec<VerifyProblem>,
+}
+
+#[napi(object, object_from_js = false)]
+pub struct VerifyProblem {
+  #[napi(
+    ts_type = "'checksumMismatch' | 'invalidHeader' | 'truncated' | 'missingEndMarker' | 'trailingData' | 'corruptCompression'"
+  )]
+  pub kind: VerifyProblemKind,
+  /// Offset in the decompressed archive of the header, or of the data, the
+  /// problem was found at.
+  pub offset: u64,
+  /// Path of the entry the problem affects, if any.
+  pub path: Option<String>,
+  pub message: String,
+}
+
+#[napi(string_enum = "camelCase")]
+/// A problem found by `Archive.verify`.
+pub enum VerifyProblemKind {
+  /// The checksum of a header does not match its content. The following
+  /// blocks are skipped until a valid header is found.
+  ChecksumMismatch,
+  /// A field of a header, such as its size, can't be parsed. The following
+  /// blocks are skipped until a valid header is found.
+  InvalidHeader,
+  /// The archive ends in the middle of a header or of the content of an
+  /// entry.
+  Truncated,
+  /// The archive ends without the two zero blocks marking its end.
+  MissingEndMarker,
+  /// Non-zero data follows the end-of-archive marker.
+  TrailingData,
+  /// The compressed stream is corrupt, for example its checksum does not
+  /// match the decompressed data.
+  CorruptCompression,
+}
+
+/// Reads `source` to the end, block by block, and collects the problems of
+/// the archive.
+///
+/// Read errors are reported as problems too, except when the input itself
+/// can't be read or a limit of the archive is exceeded.
+pub(crate) fn verify(source: &mut ArchiveSource) -> napi::Result<VerifyReport> {
+  let mut verifier = Verifier {
+    source,
+    offset: 0,
+    report: VerifyReport::default(),
+  };
+  if let Err(err) = verifier.walk() {
+    verifier.read_failure(err)?;
+  }
+  let mut report = verifier.report;
+  report.bytes_read = verifier.offset;
+  report.ok = report.problems.is_empty();
+  Ok(report)
+}
+
+struct Verifier<'a> {
+  source: &'a mut ArchiveSource,
+  /// Number of decompressed bytes read so far.
+  offset: u64,
+  report: VerifyReport,
+}
+
+impl Verifier<'_> {
+  fn walk(&mut self) -> io::Result<()> {
+    let mut header = tar::Header::new_old();
+    // Set by the extension headers for the entry which follows them.
+    let mut long_name = None;
+    let mut pax_path = None;
+    let mut pax_size = None;
+    let mut resyncing = false;
+    loop {
+      let offset = self.offset;
+      match self.fill(header.as_mut_bytes())? {
+        0 => {
+          self.problem(
+            VerifyProblemKind::MissingEndMarker,
+            offset,
+            None,
+            "the archive ends without an end-of-archive marker".to_owned(),
+          );
+          return Ok(());
+        }
+        BLOCK_SIZE => {}
+        _ => {
+          self.problem(
+            VerifyProblemKind::Truncated,
+            offset,
+            None,
+            "the archive ends in the middle of a header".to_owned(),
+          );
+          return Ok(());
+        }
+      }
+      if is_zero(header.as_bytes()) {
+        return self.end_of_archive(offset);
+      }
+      if !has_tar_checksum(header.as_bytes()) {
+        if !resyncing {
+          resyncing = true;
+          self.problem(
+            VerifyProblemKind::ChecksumMismatch,
+            offset,
+            None,
+            "the checksum of the header does not match, skipping to the next valid header"
+              .to_owned(),
+          );
+        }
+        continue;
+      }
+      resyncing = false;
+
+      let entry_type = header.entry_type();
+      let is_extension = entry_type.is_pax_local_extensions()
+        || entry_type.is_pax_global_extensions()
+        || entry_type.is_gnu_longname()
+        || entry_type.is_gnu_longlink();
+      let path = if is_extension {
+        None
+      } else {
+        let path = pax_path.take().or(long_name.take());
+        Some(String::from_utf8_lossy(path.as_deref().unwrap_or(&header.path_bytes())).into_owned())
+      };
+      let size = match header.entry_size() {
+        Ok(size) if is_extension => size,
+        Ok(size) => pax_size.take().unwrap_or(size),
+        Err(err) => {
+          resyncing = true;
+          self.problem(
+            VerifyProblemKind::InvalidHeader,
+            offset,
+            path,
+            err.to_string(),
+          );
+          continue;
+        }
+      };
+      if entry_type.is_gnu_sparse()
+        && header.as_gnu().is_some_and(|gnu| gnu.is_extended())
+        && !self.sparse_extensions()?
+      {
+        self.truncated(offset, path);
+        return Ok(());
+      }
+
+      let padding = size.next_multiple_of(BLOCK_SIZE as u64) - size;
+      let complete = if entry_type.is_pax_local_extensions() || entry_type.is_gnu_longname() {
+        let data = self.read(size)?;
+        let complete = data.len() as u64 == size && self.skip(padding)? == padding;
+        if entry_type.is_gnu_longname() {
+          let len = data.iter().position(|b| *b == 0).unwrap_or(data.len());
+          long_name = Some(data[..len].to_vec());
+        } else {
+          for record in tar::PaxExtensions::new(&data).flatten() {
+            match record.key_bytes() {
+              b"path" => pax_path = Some(record.value_bytes().to_vec()),
+              b"size" => pax_size = record.value().ok().and_then(|size| size.parse().ok()),
+              _ => {}
+            }
+          }
+        }
+        complete
+      } else {
+        self.skip(size + padding)? == size + padding
+      };
+      if !complete {
+        self.truncated(offset, path);
+        return Ok(());
+      }
+      if !is_extension {
+        self.report.entries += 1;
+      }
+    }
+  }
+
+  /// Reads the rest of the archive once its first zero block, at `offset`,
+  /// has been read. Only zeros may follow, at least one more block of them.
+  fn end_of_archive(&mut self, offset: u64) -> io::Result<()> {
+    let mut block = [0; BLOCK_SIZE];
+    let mut trailing = false;
+    loop {
+      let len = self.fill(&mut block)?;
+      if let Some(pos) = block[..len].iter().position(|b| *b != 0)
+        && !trailing
+      {
+        trailing = true;
+        self.problem(
+          VerifyProblemKind::TrailingData,
+          self.offset - (len - pos) as u64,
+          None,
+          "non-zero data follows the end-of-archive marker".to_owned(),
+        );
+      }
+      if len < BLOCK_SIZE {
+        break;
+      }
+    }
+    if !trailing && self.offset - offset < 2 * BLOCK_SIZE as u64 {
+      self.problem(
+        VerifyProblemKind::MissingEndMarker,
+        offset,
+        None,
+        "the end-of-archive marker is a single zero block".to_owned(),
+      );
+    }
+    Ok(())
+  }
+
+  /// Reads the extended headers of an old GNU sparse entry, returns whether
+  /// they are complete.
+  fn sparse_extensions(&mut self) -> io::Result<bool> {
+    let mut extension = tar::GnuExtSparseHeader::new();
+    loop {
+      if self.fill(extension.as_mut_bytes())? < BLOCK_SIZE {
+        return Ok(false);
+      }
+      if !extension.is_extended() {
+        return Ok(true);
+      }
+    }
+  }
+
+  /// Reads until `buf` is full or the archive ends.
+  fn fill(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    let mut len = 0;
+    while len < buf.len() {
+      match self.source.read(&mut buf[len..]) {
+        Ok(0) => break,
+        Ok(n) => len += n,
+        Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+        Err(err) => return Err(err),
+      }
+    }
+    self.offset += len as u64;
+    Ok(len)
+  }
+
+  /// Reads at most `len` bytes.
+  fn read(&mut self, len: u64) -> io::Result<Vec<u8>> {
+    let mut data = Vec::new();
+    (&mut *self.source).take(len).read_to_end(&mut data)?;
+    self.offset += data.len() as u64;
+    Ok(data)
+  }
+
+  /// Skips at most `len` bytes, returns how many were skipped.
+  fn skip(&mut self, len: u64) -> io::Result<u64> {
+    let skipped = io::copy(&mut (&mut *self.source).take(len), &mut io::sink())?;
+    self.offset += skipped;
+    Ok(skipped)
+  }
+
+  /// Records an error of the input, unless it must be thrown.
+  fn read_failure(&mut self, err: io::Error) -> napi::Result<()> {
+    if self.source.limits.is_exceeded() {
+      return Err(self.source.limits.exceeded_or(err.into()));
+    }
+    let kind = if err.kind() == io::ErrorKind::UnexpectedEof {
+      VerifyProblemKind::Truncated
+    } else if self.source.is_compressed() {
+      VerifyProblemKind::CorruptCompression
+    } else {
+      return Err(err.into());
+    };
+    self.problem(kind, self.offset, None, err.to_string());
+    Ok(())
+  }
+
+  fn truncated(&mut self, offset: u64, path: Option<String>) {
+    let message = match &path {
+      Some(path) => format!("the archive ends in the middle of the content of `{path}`"),
+      None => "the archive ends in the middle of an extension header".to_owned(),
+    };
+    self.problem(VerifyProblemKind::Truncated, offset, path, message);
+  }
+
+  fn problem(
+    &mut self,
+    kind: VerifyProblemKind,
+    offset: u64,
+    path: Option<String>,
+    message: String,
+  ) {
+    self.report.problems.push(VerifyProblem {
+      kind,
+      offset,
+      path,
+      message,
+    });
+  }
+}
+
+fn is_zero(block: &[u8]) -> bool {
+  block.iter().all(|b| *b == 0)
+}
+
+pub struct VerifyTask {
+  source: ArchiveSource,
+}
+
+impl VerifyTask {
+  pub(crate) fn new(source: ArchiveSource) -> Self {
+    Self { source }
+  }
+}
+
+impl Task for VerifyTask {
+  type Output = VerifyReport;
+  type JsValue = VerifyReport;
+
+  fn compute(&mut self) -> napi::Result<Self::Output> {
+    verify(&mut self.source)
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+    Ok(output)
+  }
+
+  fn reject(&mut self, env: Env, err: napi::Error) -> napi::Result<Self::JsValue> {
+    Err(self.source.limits.with_code(&env, err))
+  }
+}
@@ -57,9 +57,15 @@ const {
 })
 export default __napiModule.exports
 export const Archive = __napiModule.exports.Archive
+export const AsyncEntries = __napiModule.exports.AsyncEntries
 export const Builder = __napiModule.exports.Builder
 export const Entries = __napiModule.exports.Entries
 export const Entry = __napiModule.exports.Entry
+export const EntryChunks = __napiModule.exports.EntryChunks
 export const Header = __napiModule.exports.Header
 export const ReadonlyHeader = __napiModule.exports.ReadonlyHeader
+export const Compression = __napiModule.exports.Compression
 export const EntryType = __napiModule.exports.EntryType
+export const LinkPolicy = __napiModule.exports.LinkPolicy
+export const SkipReason = __napiModule.exports.SkipReason
+export const VerifyProblemKind = __napiModule.exports.VerifyProblemKind
@@ -109,9 +109,15 @@ const { instance: __napiInstance, module: __wasiModule, napiModule: __napiModule
 })
 module.exports = __napiModule.exports
 module.exports.Archive = __napiModule.exports.Archive
+module.exports.AsyncEntries = __napiModule.exports.AsyncEntries
 module.exports.Builder = __napiModule.exports.Builder
 module.exports.Entries = __napiModule.exports.Entries
 module.exports.Entry = __napiModule.exports.Entry
+module.exports.EntryChunks = __napiModule.exports.EntryChunks
 module.exports.Header = __napiModule.exports.Header
 module.exports.ReadonlyHeader = __napiModule.exports.ReadonlyHeader
+module.exports.Compression = __napiModule.exports.Compression
 module.exports.EntryType = __napiModule.exports.EntryType
+module.exports.LinkPolicy = __napiModule.exports.LinkPolicy
+module.exports.SkipReason = __napiModule.exports.SkipReason
+module.exports.VerifyProblemKind = __napiModule.exports.VerifyProblemKind